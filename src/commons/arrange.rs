@@ -0,0 +1,388 @@
+use crate::commons::command_args::{sort_column, ListingOps};
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::output::{Listing, Record};
+use crate::commons::table::field_text;
+use serde_json::{Number, Value};
+use std::cmp::Ordering;
+use std::ops::Range;
+
+static NO_GROUP: &str = "(none)";
+static TOTAL_LABEL: &str = "Total";
+
+impl Listing {
+    /// Applies `--sort-by`, `--group-by` and `--sum`. Rows are sorted by the
+    /// group column first so each group is contiguous. Sorting and grouping
+    /// columns missing from the `shown` ones are hidden, summed ones are not.
+    pub fn arrange(mut self, options: &ListingOps, shown: &[String]) -> JiraResult<Listing> {
+        let mut keys = options
+            .sort_by
+            .iter()
+            .map(|key| {
+                let (column, descending) = sort_column(key);
+                Ok((self.existing_column(column)?, descending))
+            })
+            .collect::<JiraResult<Vec<(usize, bool)>>>()?;
+
+        if let Some(group_by) = &options.group_by {
+            let group = self.existing_column(group_by)?;
+            let descending = keys.contains(&(group, true));
+            keys.retain(|(column, _)| *column != group);
+            keys.insert(0, (group, descending));
+            self.group_by = Some(group);
+        }
+
+        self.sums = options
+            .sum
+            .iter()
+            .map(|column| self.existing_column(column))
+            .collect::<JiraResult<Vec<usize>>>()?;
+
+        let shown = shown
+            .iter()
+            .filter_map(|name| self.column_index(name.trim()))
+            .collect::<Vec<usize>>();
+        self.hidden = keys
+            .iter()
+            .map(|(column, _)| *column)
+            .filter(|column| !shown.contains(column) && !self.sums.contains(column))
+            .collect();
+
+        if !keys.is_empty() {
+            let mut order = (0..self.rows.len()).collect::<Vec<usize>>();
+            order.sort_by(|a, b| {
+                keys.iter()
+                    .map(|(column, descending)| {
+                        compare(
+                            &self.rows[*a][*column],
+                            &self.rows[*b][*column],
+                            *descending,
+                        )
+                    })
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+            self.rows = order
+                .iter()
+                .map(|index| self.rows[*index].clone())
+                .collect();
+            if !self.sources.is_empty() {
+                self.sources = order
+                    .iter()
+                    .map(|index| self.sources[*index].clone())
+                    .collect();
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Index of the column with that name or field id, ignoring case.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .zip(&self.fields)
+            .position(|(column, field)| {
+                column.eq_ignore_ascii_case(name) || field.eq_ignore_ascii_case(name)
+            })
+    }
+
+    fn existing_column(&self, name: &str) -> JiraResult<usize> {
+        self.column_index(name).ok_or_else(|| {
+            JiraError::Config(format!(
+                "unknown column `{}`, expected one of {}",
+                name,
+                self.columns.join(", ")
+            ))
+        })
+    }
+
+    /// Name and rows of each group, in row order.
+    pub(crate) fn groups(&self) -> Vec<(String, Range<usize>)> {
+        let column = match self.group_by {
+            Some(column) => column,
+            None => return vec![],
+        };
+        let mut groups: Vec<(String, Range<usize>)> = vec![];
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let name = match field_text(&row[column]) {
+                name if name.is_empty() => NO_GROUP.to_string(),
+                name => name,
+            };
+            match groups.last_mut() {
+                Some((last, rows)) if *last == name => rows.end = index + 1,
+                _ => groups.push((name, index..index + 1)),
+            }
+        }
+        groups
+    }
+
+    /// Sum of each `--sum` column over `rows`.
+    pub(crate) fn totals(&self, rows: Range<usize>) -> Record {
+        Record {
+            columns: self
+                .sums
+                .iter()
+                .map(|column| self.columns[*column].clone())
+                .collect(),
+            values: self
+                .sums
+                .iter()
+                .map(|column| sum(self.rows[rows.clone()].iter().map(|row| &row[*column])))
+                .collect(),
+        }
+    }
+
+    /// Totals row of a table, labelled in its first shown column that is not
+    /// summed. The totals of a `group` hold its name in the group column, the
+    /// label going to the next column, or in the label when the group column
+    /// is hidden.
+    pub(crate) fn totals_text(&self, rows: Range<usize>, group: Option<&str>) -> Vec<String> {
+        let totals = self.totals(rows);
+        let mut text = vec![String::new(); self.columns.len()];
+        for (column, value) in self.sums.iter().zip(&totals.values) {
+            text[*column] = field_text(value);
+        }
+        let mut label = TOTAL_LABEL.to_string();
+        let mut group_column = None;
+        match (group, self.group_by) {
+            (Some(group), Some(column)) if !self.hidden.contains(&column) => {
+                text[column] = group.to_string();
+                group_column = Some(column);
+            }
+            (Some(group), _) => label = format!("{} ({})", TOTAL_LABEL, group),
+            _ => {}
+        }
+        if let Some(column) = (0..text.len()).find(|column| {
+            !self.sums.contains(column)
+                && !self.hidden.contains(column)
+                && Some(*column) != group_column
+        }) {
+            text[column] = label;
+        }
+        self.shown(&text)
+    }
+}
+
+/// Numbers compare numerically, everything else by its text ignoring case.
+/// Empty values sort last in both directions.
+fn compare(a: &Value, b: &Value, descending: bool) -> Ordering {
+    let (a_text, b_text) = (field_text(a), field_text(b));
+    let ordering = match (a_text.is_empty(), b_text.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        _ => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            _ => a_text.to_lowercase().cmp(&b_text.to_lowercase()),
+        },
+    };
+    match descending {
+        true => ordering.reverse(),
+        false => ordering,
+    }
+}
+
+/// Adds up numbers and numeric strings, whole sums stay integers.
+fn sum<'a>(values: impl Iterator<Item = &'a Value>) -> Value {
+    let total: f64 = values
+        .filter_map(|value| match value {
+            Value::String(text) => text.trim().parse::<f64>().ok(),
+            value => value.as_f64(),
+        })
+        .sum();
+    if total.fract() == 0.0 && total.abs() < i64::MAX as f64 {
+        Value::from(total as i64)
+    } else {
+        Number::from_f64(total).map_or(Value::Null, Value::Number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn listing() -> Listing {
+        let mut listing = Listing::new(&["key", "status", "Story Points"]);
+        listing.fields[2] = "customfield_10016".to_string();
+        listing.rows = vec![
+            vec![json!("JC-1"), json!("To Do"), json!(3)],
+            vec![json!("JC-2"), json!("Done"), json!(null)],
+            vec![json!("JC-3"), json!("done"), json!(10)],
+            vec![json!("JC-4"), json!(null), json!("2.5")],
+        ];
+        listing
+    }
+
+    fn options(sort_by: &[&str], group_by: Option<&str>, sum: &[&str]) -> ListingOps {
+        ListingOps {
+            columns: vec![],
+            sort_by: sort_by.iter().map(|key| key.to_string()).collect(),
+            group_by: group_by.map(str::to_string),
+            sum: sum.iter().map(|column| column.to_string()).collect(),
+        }
+    }
+
+    fn names(columns: &[&str]) -> Vec<String> {
+        columns.iter().map(|column| column.to_string()).collect()
+    }
+
+    fn keys(listing: &Listing) -> Vec<String> {
+        listing.rows.iter().map(|row| field_text(&row[0])).collect()
+    }
+
+    #[test]
+    fn numbers_compare_numerically() {
+        assert_eq!(compare(&json!(9), &json!(10), false), Ordering::Less);
+        assert_eq!(compare(&json!(2.5), &json!(2), false), Ordering::Greater);
+        assert_eq!(compare(&json!(9), &json!(10), true), Ordering::Greater);
+    }
+
+    #[test]
+    fn text_compares_ignoring_case() {
+        assert_eq!(
+            compare(&json!("done"), &json!("Done"), false),
+            Ordering::Equal
+        );
+        assert_eq!(compare(&json!("b"), &json!("A"), false), Ordering::Greater);
+        // A numeric string is text: "10" sorts before "9".
+        assert_eq!(compare(&json!("10"), &json!("9"), false), Ordering::Less);
+    }
+
+    #[test]
+    fn empty_values_sort_last_in_both_directions() {
+        for descending in [false, true] {
+            assert_eq!(
+                compare(&json!(null), &json!(1), descending),
+                Ordering::Greater
+            );
+            assert_eq!(compare(&json!("a"), &json!(""), descending), Ordering::Less);
+            assert_eq!(
+                compare(&json!(null), &json!(""), descending),
+                Ordering::Equal
+            );
+        }
+    }
+
+    #[test]
+    fn whole_sums_stay_integers() {
+        let values = [json!(3), json!("4"), json!(null), json!("n/a")];
+        assert_eq!(sum(values.iter()), json!(7));
+        let values = [json!(3), json!(" 2.5 ")];
+        assert_eq!(sum(values.iter()), json!(5.5));
+        assert_eq!(sum([].iter()), json!(0));
+    }
+
+    #[test]
+    fn sorts_by_several_columns() {
+        let shown = names(&["key", "status", "Story Points"]);
+        let listing = listing()
+            .arrange(
+                &options(&["status", "Story Points:desc"], None, &[]),
+                &shown,
+            )
+            .unwrap();
+        assert_eq!(keys(&listing), vec!["JC-3", "JC-2", "JC-1", "JC-4"]);
+        assert!(listing.hidden.is_empty());
+    }
+
+    #[test]
+    fn sorts_by_field_id() {
+        let shown = names(&["key"]);
+        let listing = listing()
+            .arrange(&options(&["customfield_10016:desc"], None, &[]), &shown)
+            .unwrap();
+        assert_eq!(keys(&listing), vec!["JC-3", "JC-1", "JC-4", "JC-2"]);
+    }
+
+    #[test]
+    fn unknown_columns_are_an_error() {
+        let error = listing()
+            .arrange(&options(&["priority"], None, &[]), &[])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "configuration error: unknown column `priority`, expected one of key, status, Story Points"
+        );
+    }
+
+    #[test]
+    fn groups_follow_the_group_column() {
+        let shown = names(&["key", "status"]);
+        let listing = listing()
+            .arrange(&options(&["key:desc"], Some("status"), &[]), &shown)
+            .unwrap();
+        assert_eq!(keys(&listing), vec!["JC-3", "JC-2", "JC-1", "JC-4"]);
+        assert_eq!(
+            listing.groups(),
+            vec![
+                ("done".to_string(), 0..1),
+                ("Done".to_string(), 1..2),
+                ("To Do".to_string(), 2..3),
+                ("(none)".to_string(), 3..4),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_and_group_columns_not_shown_are_hidden() {
+        let shown = names(&["key"]);
+        let listing = listing()
+            .arrange(&options(&["Story Points"], Some("status"), &[]), &shown)
+            .unwrap();
+        assert_eq!(listing.hidden, vec![1, 2]);
+        assert_eq!(listing.shown(&listing.columns), vec!["key"]);
+    }
+
+    #[test]
+    fn summed_columns_are_never_hidden() {
+        let shown = names(&["key"]);
+        let listing = listing()
+            .arrange(&options(&["Story Points"], None, &["Story Points"]), &shown)
+            .unwrap();
+        assert!(listing.hidden.is_empty());
+    }
+
+    #[test]
+    fn totals_label_goes_to_the_first_column_not_summed() {
+        let shown = names(&["key", "status", "Story Points"]);
+        let listing = listing()
+            .arrange(&options(&[], None, &["Story Points"]), &shown)
+            .unwrap();
+        assert_eq!(listing.totals_text(0..4, None), vec!["Total", "", "15.5"]);
+        assert_eq!(listing.totals_text(0..1, None), vec!["Total", "", "3"]);
+    }
+
+    #[test]
+    fn group_totals_hold_the_group_name() {
+        let shown = names(&["key", "status", "Story Points"]);
+        let listing = listing()
+            .arrange(&options(&[], Some("status"), &["Story Points"]), &shown)
+            .unwrap();
+        assert_eq!(
+            listing.totals_text(2..3, Some("To Do")),
+            vec!["Total", "To Do", "3"]
+        );
+
+        let listing = listing
+            .arrange(&options(&[], Some("key"), &["Story Points"]), &shown)
+            .unwrap();
+        assert_eq!(
+            listing.totals_text(0..1, Some("JC-1")),
+            vec!["JC-1", "Total", "3"]
+        );
+    }
+
+    #[test]
+    fn hidden_group_column_goes_into_the_label() {
+        let shown = names(&["key", "Story Points"]);
+        let listing = listing()
+            .arrange(&options(&[], Some("status"), &["Story Points"]), &shown)
+            .unwrap();
+        assert_eq!(
+            listing.totals_text(1..2, Some("done")),
+            vec!["Total (done)", "10"]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use crate::commons::config::HttpSettings;
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::structs::AuthOptions;
+use anyhow::anyhow;
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Certificate, Client, Method, Proxy, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
+
+static DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+static DEFAULT_USER_AGENT: &str = concat!("jira-cli/", env!("CARGO_PKG_VERSION"));
+static PEM_CERT_END: &str = "-----END CERTIFICATE-----";
+static DEFAULT_RETRIES: u32 = 3;
+static DEFAULT_MAX_RETRY_DELAY_SECS: u64 = 30;
+static BASE_RETRY_DELAY_MILLIS: u64 = 500;
+
+/// HTTP client shared by every handler, built once from the selected profile so
+/// connections are pooled across requests.
+#[derive(Debug, Clone)]
+pub struct JiraClient {
+    pub(crate) http: Client,
+    pub auth_options: AuthOptions,
+    retries: u32,
+    max_retry_delay: Duration,
+    /// Whether the host is Jira Cloud, asked to `/serverInfo` once per run.
+    pub(crate) cloud: Arc<OnceLock<bool>>,
+}
+
+impl JiraClient {
+    pub fn new(auth_options: AuthOptions, settings: &HttpSettings) -> Result<Self, anyhow::Error> {
+        let mut builder = Client::builder()
+            .connect_timeout(Duration::from_secs(
+                settings
+                    .connect_timeout
+                    .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+            ))
+            .user_agent(settings.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+
+        if let Some(proxy) = &settings.proxy {
+            debug!("Using proxy {}", proxy);
+            builder = builder
+                .proxy(Proxy::all(proxy).map_err(|e| anyhow!("invalid proxy {}: {}", proxy, e))?);
+        }
+
+        // reqwest 0.11 has no read timeout, only a total one that also bounds
+        // reading the body, so it is left off unless the profile asks for it.
+        if let Some(timeout) = settings.timeout {
+            builder = builder.timeout(Duration::from_secs(timeout));
+        }
+
+        for path in &settings.ca_certs {
+            for cert in load_certificates(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if settings.insecure {
+            warn!("TLS certificate verification is disabled for this profile");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(JiraClient {
+            http: builder.build()?,
+            auth_options,
+            retries: settings.retries.unwrap_or(DEFAULT_RETRIES),
+            max_retry_delay: Duration::from_secs(
+                settings
+                    .max_retry_delay
+                    .unwrap_or(DEFAULT_MAX_RETRY_DELAY_SECS),
+            ),
+            cloud: Arc::new(OnceLock::new()),
+        })
+    }
+
+    pub fn host(&self) -> &str {
+        &self.auth_options.host
+    }
+
+    /// Sends a request and turns any non successful status into a `JiraError`.
+    pub async fn send(&self, req: RequestBuilder) -> JiraResult<Response> {
+        let response = self.execute(req).await?;
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(JiraError::from_response(response).await)
+        }
+    }
+
+    /// Sends a request and deserializes its successful JSON response.
+    pub async fn send_json<T: DeserializeOwned>(&self, req: RequestBuilder) -> JiraResult<T> {
+        let response = self.send(req).await?;
+        let url = response.url().clone();
+        let body = response.text().await?;
+        serde_json::from_str(&body)
+            .map_err(|e| JiraError::deserialize(&format!("the response of {}", url.path()), e))
+    }
+
+    /// Sends a request as is, retrying it with jittered exponential backoff.
+    ///
+    /// 429 responses are retried for every method since Jira rejects them before
+    /// doing any work. 5xx responses and network errors are only retried for
+    /// idempotent methods. A `Retry-After` header replaces the computed delay,
+    /// capped by `max_retry_delay`. The response is returned as is when the
+    /// header asks to wait longer than that, rather than retrying too early.
+    pub async fn execute(&self, req: RequestBuilder) -> Result<Response, reqwest::Error> {
+        let request = req.build()?;
+        let idempotent = is_idempotent(request.method());
+        let mut attempt = 0;
+
+        loop {
+            let retry = match request.try_clone() {
+                Some(retry) if attempt < self.retries => retry,
+                _ => return self.http.execute(request).await,
+            };
+            attempt += 1;
+
+            let delay = match self.http.execute(retry).await {
+                Ok(response) => {
+                    let status = response.status();
+                    let retryable = status == StatusCode::TOO_MANY_REQUESTS
+                        || (idempotent && status.is_server_error());
+                    if !retryable {
+                        return Ok(response);
+                    }
+                    debug!(
+                        "{} {} returned {}, retry {} of {}",
+                        request.method(),
+                        request.url(),
+                        status,
+                        attempt,
+                        self.retries
+                    );
+                    match retry_after(&response) {
+                        Some(delay) if delay > self.max_retry_delay => {
+                            debug!(
+                                "Retry-After of {:?} exceeds the max retry delay, giving up",
+                                delay
+                            );
+                            return Ok(response);
+                        }
+                        Some(delay) => delay,
+                        None => self.backoff(attempt),
+                    }
+                }
+                Err(e) if idempotent && (e.is_connect() || e.is_timeout()) => {
+                    debug!(
+                        "{} {} failed: {}, retry {} of {}",
+                        request.method(),
+                        request.url(),
+                        e,
+                        attempt,
+                        self.retries
+                    );
+                    self.backoff(attempt)
+                }
+                Err(e) => return Err(e),
+            };
+
+            debug!("Waiting {:?} before retrying", delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Exponential delay for `attempt` with full jitter over its upper half.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = BASE_RETRY_DELAY_MILLIS.saturating_mul(1 << attempt.min(16));
+        let capped = exponential.min(self.max_retry_delay.as_millis() as u64);
+        Duration::from_millis(rand::thread_rng().gen_range(capped / 2, capped + 1))
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+/// Reads a `Retry-After` header given either in seconds or as an HTTP date.
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, SystemTime::now())
+}
+
+/// Delay a `Retry-After` value asks for at `now`, a date in the past meaning
+/// no delay.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    match value.trim().parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value.trim())
+            .ok()
+            .map(|date| date.duration_since(now).unwrap_or_default()),
+    }
+}
+
+/// Reads every certificate of a PEM bundle.
+fn load_certificates(path: &str) -> Result<Vec<Certificate>, anyhow::Error> {
+    let pem = fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read CA certificate {}: {}", path, e))?;
+
+    let certs = pem
+        .split_inclusive(PEM_CERT_END)
+        .filter(|cert| cert.contains(PEM_CERT_END))
+        .map(|cert| {
+            Certificate::from_pem(cert.trim().as_bytes())
+                .map_err(|e| anyhow!("invalid CA certificate in {}: {}", path, e))
+        })
+        .collect::<Result<Vec<Certificate>, anyhow::Error>>()?;
+
+    if certs.is_empty() {
+        return Err(anyhow!("no PEM certificate found in {}", path));
+    }
+    debug!("Loaded {} CA certificate(s) from {}", certs.len(), path);
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn retry_after_in_seconds() {
+        let now = SystemTime::now();
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn retry_after_as_http_date() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        let date = 784111777;
+        let value = "Sun, 06 Nov 1994 08:49:37 GMT";
+        assert_eq!(
+            parse_retry_after(value, at(date - 30)),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after(value, at(date + 30)),
+            Some(Duration::from_secs(0))
+        );
+    }
+
+    #[test]
+    fn retry_after_invalid() {
+        let now = SystemTime::now();
+        assert_eq!(parse_retry_after("", now), None);
+        assert_eq!(parse_retry_after("-5", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn idempotent_methods() {
+        assert!(is_idempotent(&Method::GET));
+        assert!(is_idempotent(&Method::PUT));
+        assert!(is_idempotent(&Method::DELETE));
+        assert!(!is_idempotent(&Method::POST));
+        assert!(!is_idempotent(&Method::PATCH));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::commons::output::RenderOptions;
+use structopt::StructOpt;
+
+/// Column, sorting and grouping options shared by the issue list commands.
+#[derive(StructOpt, Debug)]
+pub struct ListingOps {
+    #[structopt(
+        long = "columns",
+        alias = "fields",
+        help = "Comma separated field ids or custom field names to show.",
+        use_delimiter = true
+    )]
+    pub columns: Vec<String>,
+    #[structopt(
+        long = "sort-by",
+        help = "Comma separated columns to sort by, each optionally followed by :asc or :desc.",
+        use_delimiter = true
+    )]
+    pub sort_by: Vec<String>,
+    #[structopt(long = "group-by", help = "Column to group issues by.")]
+    pub group_by: Option<String>,
+    #[structopt(
+        long = "sum",
+        help = "Comma separated numeric columns to total, e.g. \"Story Points\". Summed columns are shown even when --columns leaves them out.",
+        use_delimiter = true
+    )]
+    pub sum: Vec<String>,
+}
+
+impl ListingOps {
+    /// Columns to show, from `--columns`, `--format` or the defaults.
+    pub fn shown_columns(
+        &self,
+        render: &RenderOptions,
+        command: &str,
+        builtin: &[&str],
+    ) -> Vec<String> {
+        render.columns(command, &self.columns, builtin)
+    }
+
+    /// The `shown` columns followed by the ones sorting, grouping and totals
+    /// read, the former only being hidden once the listing is arranged.
+    pub fn column_names(&self, shown: &[String]) -> Vec<String> {
+        let mut names = shown.to_vec();
+        names.extend(
+            self.sort_by
+                .iter()
+                .map(|key| sort_column(key).0.to_string()),
+        );
+        names.extend(self.group_by.iter().cloned());
+        names.extend(self.sum.iter().cloned());
+        names
+    }
+}
+
+/// Splits a `--sort-by` entry into its column and whether it sorts descending.
+pub fn sort_column(key: &str) -> (&str, bool) {
+    let key = key.trim();
+    match key.rsplit_once(':') {
+        Some((column, order)) if order.eq_ignore_ascii_case("desc") => (column.trim(), true),
+        Some((column, order)) if order.eq_ignore_ascii_case("asc") => (column.trim(), false),
+        _ => (key, false),
+    }
+}
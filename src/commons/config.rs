@@ -0,0 +1,605 @@
+use crate::commons::client::JiraClient;
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::file_utilities::load_yaml;
+use crate::commons::structs::{AuthMethod, AuthOptions};
+use anyhow::{anyhow, bail};
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use tokio::process::Command;
+
+pub static CONF_PATH: &str = "/.jira-cli/conf.yaml";
+/// Profile name given to the legacy top level `jira:` block.
+pub static LEGACY_PROFILE: &str = "default";
+/// Profile name used when everything comes from flags and environment variables.
+pub static ENV_PROFILE: &str = "environment";
+
+pub fn conf_path() -> Result<String, anyhow::Error> {
+    let home_dir = home_dir().ok_or_else(|| anyhow!("could not find the home directory"))?;
+    Ok(format!("{}{}", home_dir.to_string_lossy(), CONF_PATH))
+}
+
+/// Typed representation of conf.yaml.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Legacy single instance block, exposed as the `default` profile.
+    pub jira: Option<Profile>,
+    /// Default `--columns` of the list commands, keyed by command name.
+    #[serde(default)]
+    pub columns: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub host: Option<String>,
+    pub auth: Option<AuthKind>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+    pub password_command: Option<String>,
+    #[serde(default)]
+    pub http: HttpSettings,
+}
+
+/// Transport settings of a profile, declared under its `http` key.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpSettings {
+    /// Opt-in total time of a request, in seconds, from connecting to reading
+    /// the last byte of the body. Unset, a request may take as long as data
+    /// keeps arriving. reqwest has no timeout between two reads, so a slow,
+    /// large response fails once this is reached.
+    pub timeout: Option<u64>,
+    /// Connection timeout, in seconds.
+    pub connect_timeout: Option<u64>,
+    pub proxy: Option<String>,
+    /// PEM files with extra root certificates to trust.
+    #[serde(default)]
+    pub ca_certs: Vec<String>,
+    /// Skips TLS certificate verification.
+    #[serde(default)]
+    pub insecure: bool,
+    pub user_agent: Option<String>,
+    /// How many times a rate limited or failed request is retried.
+    pub retries: Option<u32>,
+    /// Upper bound of the delay between two retries, in seconds.
+    pub max_retry_delay: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthKind {
+    Basic,
+    ApiToken,
+    Bearer,
+    Anonymous,
+}
+
+impl fmt::Display for AuthKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuthKind::Basic => "basic",
+            AuthKind::ApiToken => "api_token",
+            AuthKind::Bearer => "bearer",
+            AuthKind::Anonymous => "anonymous",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Connection settings given as flags or through their `JIRA_CLI_*` environment
+/// variables. They take precedence over anything read from conf.yaml.
+#[derive(Debug, Default)]
+pub struct ConnectionFlags {
+    pub profile: Option<String>,
+    pub host: Option<String>,
+    pub user: Option<String>,
+    pub token: Option<String>,
+}
+
+/// Where a resolved value came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueSource {
+    FlagOrEnv,
+    PasswordCommand,
+    ConfFile,
+}
+
+impl fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            ValueSource::FlagOrEnv => "flag or environment",
+            ValueSource::PasswordCommand => "password_command",
+            ValueSource::ConfFile => "conf.yaml",
+        };
+        write!(f, "{}", source)
+    }
+}
+
+/// A profile after flags, environment variables and `password_command` were applied.
+#[derive(Debug, Clone)]
+pub struct ResolvedProfile {
+    pub name: String,
+    pub host: String,
+    pub host_source: ValueSource,
+    pub auth: AuthMethod,
+    pub secret_source: Option<ValueSource>,
+    pub http: HttpSettings,
+    pub columns: BTreeMap<String, Vec<String>>,
+}
+
+struct Secret {
+    value: String,
+    is_token: bool,
+    source: ValueSource,
+}
+
+impl Config {
+    /// Reads and parses conf.yaml, returning `None` when the file does not exist.
+    pub async fn load(conf_path: &str) -> Result<Option<Config>, anyhow::Error> {
+        if !Path::new(conf_path).exists() {
+            return Ok(None);
+        }
+        debug!("Loading configuration from {}", conf_path);
+
+        let conf_string = load_yaml(conf_path).await?;
+        if conf_string.trim().is_empty() {
+            bail!("{} is empty, run `jira-cli config init`", conf_path);
+        }
+        serde_yaml::from_str::<Config>(&conf_string)
+            .map(Some)
+            .map_err(|e| anyhow!("{} is invalid: {}", conf_path, e))
+    }
+
+    /// Every declared profile, including the legacy `jira:` block.
+    pub fn all_profiles(&self) -> Vec<(String, &Profile)> {
+        let mut profiles: Vec<(String, &Profile)> = self
+            .profiles
+            .iter()
+            .map(|(name, profile)| (name.clone(), profile))
+            .collect();
+        if let Some(profile) = &self.jira {
+            profiles.push((LEGACY_PROFILE.to_string(), profile));
+        }
+        profiles
+    }
+
+    /// Picks the profile named `wanted`, then `default_profile`, then the only one declared.
+    pub fn select_profile(&self, wanted: Option<&str>) -> Result<(String, Profile), anyhow::Error> {
+        let profiles = self.all_profiles();
+        let names = profiles
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ");
+
+        let wanted = match wanted.or(self.default_profile.as_deref()) {
+            Some(wanted) => wanted.to_string(),
+            None => match profiles.as_slice() {
+                [(name, _)] => name.clone(),
+                [] => bail!("no profiles found, declare them under the `profiles` key"),
+                _ => bail!(
+                    "several profiles found ({}), use --profile or set `default_profile`",
+                    names
+                ),
+            },
+        };
+
+        match profiles.into_iter().find(|(name, _)| name == &wanted) {
+            Some((name, profile)) => Ok((name, profile.clone())),
+            None => bail!(
+                "profile {} not found, available profiles: {}",
+                &wanted,
+                names
+            ),
+        }
+    }
+}
+
+/// Resolves the selected profile.
+///
+/// The profile is taken from `--profile` (or `JIRA_CLI_PROFILE`), then from the
+/// `default_profile` key, then from the only profile declared.
+///
+/// Each value is resolved as flags > env > `password_command` > file, so conf.yaml
+/// may be missing entirely when the host and credentials come from the environment.
+/// Its profiles are then ignored unless `--profile` names one.
+pub async fn resolve_profile(flags: &ConnectionFlags) -> Result<ResolvedProfile, anyhow::Error> {
+    let conf_path = conf_path()?;
+    let config = Config::load(&conf_path).await?;
+    resolve_config(config, flags, &conf_path, true).await
+}
+
+/// Resolves the selected profile like `resolve_profile` without running its
+/// `password_command`, the secret it would print being left empty.
+pub async fn describe_profile(flags: &ConnectionFlags) -> Result<ResolvedProfile, anyhow::Error> {
+    let conf_path = conf_path()?;
+    let config = Config::load(&conf_path).await?;
+    resolve_config(config, flags, &conf_path, false).await
+}
+
+/// Resolves the profile of `config`, read from `conf_path`, see `resolve_profile`.
+async fn resolve_config(
+    config: Option<Config>,
+    flags: &ConnectionFlags,
+    conf_path: &str,
+    run_command: bool,
+) -> Result<ResolvedProfile, anyhow::Error> {
+    let from_flags = flags.profile.is_none() && flags.host.is_some() && flags.token.is_some();
+    let (name, profile, columns) = match config {
+        Some(config) if from_flags => {
+            debug!(
+                "Host and token given, ignoring the profiles of {}",
+                conf_path
+            );
+            (ENV_PROFILE.to_string(), Profile::default(), config.columns)
+        }
+        Some(config) => {
+            let (name, profile) = config.select_profile(flags.profile.as_deref())?;
+            (name, profile, config.columns)
+        }
+        None if flags.host.is_some() => {
+            debug!("{} not found, using flags and environment only", conf_path);
+            (ENV_PROFILE.to_string(), Profile::default(), BTreeMap::new())
+        }
+        None => bail!(
+            "{} not found, run `jira-cli config init` or set JIRA_CLI_HOST and credentials",
+            conf_path
+        ),
+    };
+    debug!("Using profile {}", &name);
+
+    let (host, host_source) = match (&flags.host, &profile.host) {
+        (Some(host), _) => (host.clone(), ValueSource::FlagOrEnv),
+        (None, Some(host)) => (host.clone(), ValueSource::ConfFile),
+        (None, None) => bail!("profile {} is missing the `host` key", &name),
+    };
+    let user = flags.user.clone().or_else(|| profile.user.clone());
+    let secret = resolve_secret(&profile, &name, flags.token.clone(), run_command).await?;
+    let secret_source = secret.as_ref().map(|secret| secret.source);
+
+    Ok(ResolvedProfile {
+        auth: auth_method(&profile, &name, user, secret)?,
+        name,
+        host,
+        host_source,
+        secret_source,
+        http: profile.http,
+        columns,
+    })
+}
+
+/// Resolves the selected profile, see `resolve_profile`.
+pub async fn load_profile(flags: &ConnectionFlags) -> JiraResult<ResolvedProfile> {
+    resolve_profile(flags)
+        .await
+        .map_err(|e| JiraError::Config(e.to_string()))
+}
+
+impl ResolvedProfile {
+    /// Builds the `JiraClient` used by every handler.
+    pub fn client(&self) -> JiraResult<JiraClient> {
+        let auth_options = AuthOptions {
+            host: self.host.clone(),
+            auth: self.auth.clone(),
+        };
+        JiraClient::new(auth_options, &self.http).map_err(|e| JiraError::Config(e.to_string()))
+    }
+}
+
+/// Resolves the profile secret as `--token`/`JIRA_CLI_TOKEN`, then the output of
+/// `password_command`, then the plain `token` or `password` keys. The command
+/// is only run when `run_command` is set.
+async fn resolve_secret(
+    profile: &Profile,
+    name: &str,
+    token_flag: Option<String>,
+    run_command: bool,
+) -> Result<Option<Secret>, anyhow::Error> {
+    if let Some(token) = token_flag {
+        return Ok(Some(Secret {
+            value: token,
+            is_token: true,
+            source: ValueSource::FlagOrEnv,
+        }));
+    }
+    if let Some(command) = &profile.password_command {
+        let secret = match run_command {
+            true => run_password_command(command)
+                .await
+                .map_err(|e| anyhow!("password_command of profile {} failed: {}", name, e))?,
+            false => String::new(),
+        };
+        return Ok(Some(Secret {
+            value: secret,
+            is_token: false,
+            source: ValueSource::PasswordCommand,
+        }));
+    }
+    if let Some(token) = &profile.token {
+        return Ok(Some(Secret {
+            value: token.clone(),
+            is_token: true,
+            source: ValueSource::ConfFile,
+        }));
+    }
+    Ok(profile.password.as_ref().map(|password| Secret {
+        value: password.clone(),
+        is_token: false,
+        source: ValueSource::ConfFile,
+    }))
+}
+
+/// Runs `command` through the shell and returns the first line of its stdout.
+async fn run_password_command(command: &str) -> Result<String, anyhow::Error> {
+    debug!("Running password command {}", command);
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output().await?
+    } else {
+        Command::new("sh").args(["-c", command]).output().await?
+    };
+
+    if !output.status.success() {
+        bail!(
+            "`{}` exited with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    match stdout.lines().next() {
+        Some(secret) if !secret.is_empty() => Ok(secret.to_string()),
+        _ => bail!("`{}` printed nothing", command),
+    }
+}
+
+/// Uses the `auth` key of a profile. When it is absent the method is inferred
+/// from the credentials resolved: user and password means `basic`, user and token
+/// means `api_token`, a lone secret means `bearer` and nothing means `anonymous`.
+fn auth_method(
+    profile: &Profile,
+    name: &str,
+    user: Option<String>,
+    secret: Option<Secret>,
+) -> Result<AuthMethod, anyhow::Error> {
+    let method = match profile.auth {
+        Some(method) => method,
+        None => match (&user, &secret) {
+            (Some(_), Some(secret)) if secret.is_token => AuthKind::ApiToken,
+            (Some(_), Some(_)) => AuthKind::Basic,
+            (None, Some(_)) => AuthKind::Bearer,
+            _ => AuthKind::Anonymous,
+        },
+    };
+
+    let missing = |what: &str| anyhow!("profile {} uses {} auth but has no {}", name, method, what);
+    let secret = secret.map(|secret| secret.value);
+
+    match method {
+        AuthKind::Basic => Ok(AuthMethod::Basic {
+            user: user.ok_or_else(|| missing("user"))?,
+            password: secret.ok_or_else(|| missing("password"))?,
+        }),
+        AuthKind::ApiToken => Ok(AuthMethod::ApiToken {
+            email: user.ok_or_else(|| missing("user"))?,
+            token: secret.ok_or_else(|| missing("token"))?,
+        }),
+        AuthKind::Bearer => Ok(AuthMethod::Bearer {
+            token: secret.ok_or_else(|| missing("token"))?,
+        }),
+        AuthKind::Anonymous => Ok(AuthMethod::Anonymous),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONF: &str = "/home/jdoe/.jira-cli/conf.yaml";
+
+    fn config(yaml: &str) -> Option<Config> {
+        Some(serde_yaml::from_str(yaml).unwrap())
+    }
+
+    fn flags(host: Option<&str>, user: Option<&str>, token: Option<&str>) -> ConnectionFlags {
+        ConnectionFlags {
+            profile: None,
+            host: host.map(str::to_string),
+            user: user.map(str::to_string),
+            token: token.map(str::to_string),
+        }
+    }
+
+    fn two_profiles() -> Option<Config> {
+        config(
+            "profiles:\n  \
+               production:\n    host: https://jira.example.com\n    user: jdoe\n    password: secret\n  \
+               staging:\n    host: https://staging.example.com\n    token: pat\n\
+             columns:\n  search: [key]\n",
+        )
+    }
+
+    #[tokio::test]
+    async fn flags_win_over_the_profile() {
+        let config = config(
+            "profiles:\n  main:\n    host: https://jira.example.com\n    user: jdoe\n    password: secret\n",
+        );
+        let flags = flags(Some("https://other.example.com"), Some("ana"), Some("t0k"));
+        let mut with_profile = flags;
+        with_profile.profile = Some("main".to_string());
+
+        let profile = resolve_config(config, &with_profile, CONF, true)
+            .await
+            .unwrap();
+        assert_eq!(profile.name, "main");
+        assert_eq!(profile.host, "https://other.example.com");
+        assert_eq!(profile.host_source, ValueSource::FlagOrEnv);
+        assert_eq!(profile.secret_source, Some(ValueSource::FlagOrEnv));
+        assert_eq!(
+            profile.auth,
+            AuthMethod::ApiToken {
+                email: "ana".to_string(),
+                token: "t0k".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn host_and_token_from_flags_skip_profile_selection() {
+        let flags = flags(Some("https://jira.example.com"), None, Some("pat"));
+        let profile = resolve_config(two_profiles(), &flags, CONF, true)
+            .await
+            .unwrap();
+        assert_eq!(profile.name, ENV_PROFILE);
+        assert_eq!(
+            profile.auth,
+            AuthMethod::Bearer {
+                token: "pat".to_string()
+            }
+        );
+        assert_eq!(profile.columns["search"], vec!["key"]);
+    }
+
+    #[tokio::test]
+    async fn several_profiles_need_a_choice() {
+        let flags = flags(Some("https://jira.example.com"), None, None);
+        let error = resolve_config(two_profiles(), &flags, CONF, true)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().starts_with("several profiles found"));
+
+        let mut flags = flags;
+        flags.profile = Some("staging".to_string());
+        let profile = resolve_config(two_profiles(), &flags, CONF, true)
+            .await
+            .unwrap();
+        assert_eq!(profile.host, "https://jira.example.com");
+        assert_eq!(profile.secret_source, Some(ValueSource::ConfFile));
+    }
+
+    #[tokio::test]
+    async fn missing_conf_needs_a_host() {
+        let error = resolve_config(None, &flags(None, None, Some("pat")), CONF, true)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().starts_with(CONF));
+
+        let flags = flags(Some("https://jira.example.com"), None, None);
+        let profile = resolve_config(None, &flags, CONF, true).await.unwrap();
+        assert_eq!(profile.auth, AuthMethod::Anonymous);
+    }
+
+    #[tokio::test]
+    async fn password_command_wins_over_the_file() {
+        let config = config(
+            "jira:\n  host: https://jira.example.com\n  user: jdoe\n  password: plain\n  \
+             password_command: printf 'from-command\\nignored'\n",
+        );
+        let profile = resolve_config(config, &flags(None, None, None), CONF, true)
+            .await
+            .unwrap();
+        assert_eq!(profile.name, LEGACY_PROFILE);
+        assert_eq!(profile.secret_source, Some(ValueSource::PasswordCommand));
+        assert_eq!(
+            profile.auth,
+            AuthMethod::Basic {
+                user: "jdoe".to_string(),
+                password: "from-command".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn password_command_is_not_run_to_describe_a_profile() {
+        let config = config(
+            "jira:\n  host: https://jira.example.com\n  user: jdoe\n  password_command: exit 3\n",
+        );
+        let profile = resolve_config(config, &flags(None, None, None), CONF, false)
+            .await
+            .unwrap();
+        assert_eq!(profile.secret_source, Some(ValueSource::PasswordCommand));
+    }
+
+    #[tokio::test]
+    async fn failing_password_command() {
+        let config =
+            config("jira:\n  host: https://jira.example.com\n  password_command: exit 3\n");
+        let error = resolve_config(config, &flags(None, None, None), CONF, true)
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("password_command of profile default failed: `exit 3` exited with"));
+    }
+
+    fn secret(value: &str, is_token: bool) -> Option<Secret> {
+        Some(Secret {
+            value: value.to_string(),
+            is_token,
+            source: ValueSource::ConfFile,
+        })
+    }
+
+    #[test]
+    fn auth_method_is_inferred_from_the_credentials() {
+        let profile = Profile::default();
+        let user = || Some("jdoe".to_string());
+        let infer = |user, secret| auth_method(&profile, "main", user, secret).unwrap();
+
+        assert_eq!(
+            infer(user(), secret("pw", false)),
+            AuthMethod::Basic {
+                user: "jdoe".to_string(),
+                password: "pw".to_string()
+            }
+        );
+        assert_eq!(
+            infer(user(), secret("tk", true)),
+            AuthMethod::ApiToken {
+                email: "jdoe".to_string(),
+                token: "tk".to_string()
+            }
+        );
+        assert_eq!(
+            infer(None, secret("tk", true)),
+            AuthMethod::Bearer {
+                token: "tk".to_string()
+            }
+        );
+        assert_eq!(infer(user(), None), AuthMethod::Anonymous);
+    }
+
+    #[test]
+    fn declared_auth_method_needs_its_credentials() {
+        let profile = Profile {
+            auth: Some(AuthKind::Basic),
+            ..Default::default()
+        };
+        let error = auth_method(&profile, "main", None, secret("pw", false)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "profile main uses basic auth but has no user"
+        );
+
+        let profile = Profile {
+            auth: Some(AuthKind::Bearer),
+            ..Default::default()
+        };
+        let user = Some("jdoe".to_string());
+        assert_eq!(
+            auth_method(&profile, "main", user, secret("pw", false)).unwrap(),
+            AuthMethod::Bearer {
+                token: "pw".to_string()
+            }
+        );
+    }
+}
@@ -1,14 +1,21 @@
+use crate::commons::client::JiraClient;
+use crate::commons::errors::{JiraError, JiraResult};
 use crate::commons::file_utilities::{json_from_file, json_to_file};
+use crate::commons::output::Column;
 use crate::commons::req_builder::build_get_req;
-use crate::commons::structs::{AuthOptions, REST_URI};
+use crate::commons::structs::REST_URI;
 use crate::projects::projects_structs::Project;
-use serde::Deserialize;
-use std::collections::hash_map::RandomState;
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
 use url::Url;
 
-const FILE_CACHE_PATH: &str = ".";
+const CACHE_DIR: &str = ".jira-cli/cache";
 const SEARCH_URI: &str = "/issue/createmeta?";
+pub const CUSTOM_FIELD_PREFIX: &str = "customfield_";
 
 pub type CustomFieldsCache = HashMap<String, String>;
 
@@ -16,144 +23,420 @@ pub struct CustomFieldsHandler;
 
 #[derive(Debug, Clone, Deserialize)]
 struct ProjectCustomFields {
-    expand: String,
     projects: Vec<Project>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct CustomFields {
-    id: String,
-    name: String,
-    custom: Option<bool>,
-    orderable: Option<bool>,
-    navigable: Option<bool>,
-    searchable: Option<bool>,
-    #[serde(rename = "clauseNames")]
-    clause_names: Vec<String>,
-    schema: Option<Schema>,
-}
-
-#[derive(Debug, Clone, Deserialize)]
+/// Type of the value a field holds, `items` being the type of the elements
+/// of `array` fields.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Schema {
     #[serde(rename = "type")]
-    issue_type: Option<String>,
-    custom: Option<String>,
-    custom_id: Option<i32>,
-    items: Option<String>,
-    system: Option<String>,
+    pub field_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom: Option<String>,
+    #[serde(rename = "customId", skip_serializing_if = "Option::is_none")]
+    pub custom_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+}
+
+/// A field of the create screen of a project as createmeta describes it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldMeta {
+    pub name: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub has_default_value: bool,
+    #[serde(default)]
+    pub schema: Schema,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_values: Vec<Value>,
+}
+
+/// Every field of the create screen of a project, system ones included, by id.
+pub type FieldSchemas = HashMap<String, FieldMeta>;
+
+/// Create screen fields of each issue type of a project, by issue type name.
+pub type IssueTypeSchemas = HashMap<String, FieldSchemas>;
+
+/// Cache files of the fields of a project, under a directory per Jira host
+/// since custom field ids differ between instances.
+struct CachePaths {
+    custom_fields: String,
+    reversed: String,
+    schemas: String,
 }
 
 impl CustomFieldsHandler {
     async fn save_custom_fields(
         &self,
-        auth_options: &AuthOptions,
+        client: &JiraClient,
         project: &str,
-        cache_path: &str,
-        reversed_cache_path: &str,
-    ) -> Option<(
-        HashMap<String, String, RandomState>,
-        HashMap<String, String, RandomState>,
-    )> {
+        paths: &CachePaths,
+    ) -> JiraResult<(CustomFieldsCache, CustomFieldsCache, IssueTypeSchemas)> {
         let url = Url::parse(&format!(
             "{}{}{}projectKeys={}&expand=projects.issuetypes.fields",
-            &auth_options.host, &REST_URI, &SEARCH_URI, project,
-        ))
-        .unwrap();
-
-        let fields = build_get_req(url, auth_options)
-            .send()
-            .await
-            .unwrap()
-            .json::<ProjectCustomFields>()
-            .await
-            .unwrap();
-
-        let fields = fields.projects[0].clone().issuetypes.unwrap()[0]
-            .clone()
-            .fields
-            .unwrap()
-            .unmapped_fields;
+            client.host(),
+            &REST_URI,
+            &SEARCH_URI,
+            project,
+        ))?;
+
+        let fields = client
+            .send_json::<ProjectCustomFields>(build_get_req(url, client))
+            .await?;
+
+        let issuetypes = fields
+            .projects
+            .into_iter()
+            .next()
+            .ok_or_else(|| JiraError::NotFound(format!("project {}", project)))?
+            .issuetypes
+            .unwrap_or_default();
+
+        let mut schemas = IssueTypeSchemas::new();
+        for issuetype in &issuetypes {
+            let fields = match &issuetype.fields {
+                Some(fields) => &fields.unmapped_fields,
+                None => continue,
+            };
+            let mut type_schemas = FieldSchemas::new();
+            for (key, value) in fields {
+                match serde_json::from_value::<FieldMeta>(value.clone()) {
+                    Ok(meta) => {
+                        type_schemas.insert(key.clone(), meta);
+                    }
+                    Err(e) => debug!("No schema for field {}: {}", key, e),
+                }
+            }
+            schemas.insert(issuetype.name.clone(), type_schemas);
+        }
+
+        if issuetypes
+            .iter()
+            .all(|issuetype| issuetype.fields.is_none())
+        {
+            return Err(JiraError::NotFound(format!(
+                "issue type fields of project {} in createmeta",
+                project
+            )));
+        }
 
+        // Names of the custom fields of every issue type, a field missing
+        // from the screen of one type being found on another.
         let mut custom_fields_map: HashMap<String, String> = HashMap::new();
         let mut reversed_fields_map: HashMap<String, String> = HashMap::new();
+        let fields = issuetypes
+            .into_iter()
+            .filter_map(|issuetype| issuetype.fields)
+            .flat_map(|fields| fields.unmapped_fields);
 
         for (key, value) in fields {
             if key.contains("customfield") {
-                let value = value.get("name").unwrap().as_str().unwrap().to_string();
+                let value = match value.get("name").and_then(|name| name.as_str()) {
+                    Some(name) => name.to_string(),
+                    None => continue,
+                };
                 custom_fields_map.insert(key.clone(), value.clone());
 
                 let reversed_key = value;
-                let parse_key: Vec<&str> = key.split("_").collect();
+                let parse_key: Vec<&str> = key.split('_').collect();
                 let reversed_value = format!("cf[{}]", parse_key[1]);
                 reversed_fields_map.insert(reversed_key, reversed_value);
             }
         }
 
-        match json_to_file::<&CustomFieldsCache>(&custom_fields_map, &cache_path).await {
+        match json_to_file::<&CustomFieldsCache>(&custom_fields_map, &paths.custom_fields).await {
             Ok(_) => {
-                debug!("Custom Fields Cache File created at {}", &cache_path);
+                debug!(
+                    "Custom Fields Cache File created at {}",
+                    &paths.custom_fields
+                );
             }
             Err(e) => {
                 error!("Failed to create Custom Field File Cache {}", e);
             }
         };
 
-        match json_to_file::<&CustomFieldsCache>(&reversed_fields_map, &reversed_cache_path).await {
+        match json_to_file::<&CustomFieldsCache>(&reversed_fields_map, &paths.reversed).await {
             Ok(()) => debug!(
                 "Reversed Custom Fields Cache File created at {}",
-                &reversed_cache_path
+                &paths.reversed
             ),
             Err(e) => error!("Failed to create Reversed Custom Field File Cache {}", e),
         };
-        Some((custom_fields_map, reversed_fields_map))
+        match json_to_file::<&IssueTypeSchemas>(&schemas, &paths.schemas).await {
+            Ok(()) => debug!("Field Schemas Cache File created at {}", &paths.schemas),
+            Err(e) => error!("Failed to create Field Schemas File Cache {}", e),
+        };
+        Ok((custom_fields_map, reversed_fields_map, schemas))
     }
 
-    pub async fn get_or_cache(
+    /// Resolves `--columns` entries: issue attributes and field ids are used as
+    /// is, other names are looked up among the custom fields of `project`.
+    /// Entries naming the same field are only kept once.
+    pub async fn resolve_columns(
+        &self,
+        client: &JiraClient,
+        project: Option<&str>,
+        names: &[String],
+    ) -> JiraResult<Vec<Column>> {
+        let custom_fields = match project {
+            Some(project) => self.get_or_cache(client, project).await?.0,
+            None => CustomFieldsCache::new(),
+        };
+
+        let mut columns: Vec<Column> = vec![];
+        for name in names {
+            let column = resolve_column(name.trim(), &custom_fields, project)?;
+            if !columns.iter().any(|known| known.field == column.field) {
+                columns.push(column);
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Field id of a field id or custom field name of `project`, looked up
+    /// among the fields of `issue_type` first, then of every issue type.
+    pub async fn resolve_field(
         &self,
-        auth_options: &AuthOptions,
+        client: &JiraClient,
         project: &str,
-    ) -> Option<(CustomFieldsCache, CustomFieldsCache)> {
-        let reversed_cache_path = format!(
-            "{}/custom_fields_{}.reversed.json",
-            &FILE_CACHE_PATH, &project
-        );
-        info!("Reversed cache path {}", &reversed_cache_path);
-
-        let cache_path = format!("{}/custom_fields_{}.json", &FILE_CACHE_PATH, &project);
-        info!("Cache path {}", &cache_path);
-
-        let custom_fields: Option<CustomFieldsCache> =
-            match json_from_file::<CustomFieldsCache>(&cache_path).await {
-                Ok(custom_fields) => match custom_fields {
-                    Ok(cf) => Some(cf),
-                    _ => None,
-                },
-                _ => None,
-            };
+        issue_type: Option<&str>,
+        name: &str,
+    ) -> JiraResult<String> {
+        let (custom_fields, _) = self.get_or_cache(client, project).await?;
+        if let Some(type_fields) = self.issue_type_fields(client, project, issue_type).await? {
+            if let Some(id) = unique_field(&type_fields, name.trim(), true, Some(project))? {
+                return Ok(id);
+            }
+        }
+        resolve_column(name.trim(), &custom_fields, Some(project)).map(|column| column.field)
+    }
+
+    /// Replaces the custom field names among the keys of `fields` by the ids
+    /// of these fields in `project`. A key is matched against the names as
+    /// written, then ignoring case unless it is a `customfield_` id, and is
+    /// kept when it looks like a system field id such as `labels`. Each match
+    /// is tried among the fields of `issue_type` first, then of every issue
+    /// type.
+    pub async fn resolve_field_keys(
+        &self,
+        client: &JiraClient,
+        project: &str,
+        issue_type: Option<&str>,
+        fields: HashMap<String, Value>,
+    ) -> JiraResult<HashMap<String, Value>> {
+        let (custom_fields, _) = self.get_or_cache(client, project).await?;
+        let type_fields = self.issue_type_fields(client, project, issue_type).await?;
+        let caches = type_fields
+            .iter()
+            .chain(std::iter::once(&custom_fields))
+            .collect::<Vec<&CustomFieldsCache>>();
+        let find = |name: &str, ignore_case: bool| -> JiraResult<Option<String>> {
+            for cache in &caches {
+                if let Some(id) = unique_field(cache, name, ignore_case, Some(project))? {
+                    return Ok(Some(id));
+                }
+            }
+            Ok(None)
+        };
 
-        let reversed_custom_fields: Option<CustomFieldsCache> =
-            match json_from_file::<CustomFieldsCache>(&reversed_cache_path).await {
-                Ok(custom_fields) => match custom_fields {
-                    Ok(rcf) => Some(rcf),
-                    _ => None,
+        let mut resolved: HashMap<String, Value> = HashMap::new();
+        let mut names: HashMap<String, String> = HashMap::new();
+        for (name, value) in fields {
+            let id = match find(&name, false)? {
+                Some(id) => id,
+                None if name.starts_with(CUSTOM_FIELD_PREFIX) => name.clone(),
+                None => match find(&name, true)? {
+                    Some(id) => id,
+                    None if is_field_id(&name) => name.clone(),
+                    None => {
+                        return Err(JiraError::NotFound(format!(
+                            "field `{}` in project {}",
+                            name, project
+                        )))
+                    }
                 },
-                _ => None,
             };
-
-        match custom_fields {
-            Some(cf) => match reversed_custom_fields {
-                Some(rcf) => Some((cf, rcf)),
-                _ => None,
-            },
-            None => {
-                match self
-                    .save_custom_fields(auth_options, project, &cache_path, &reversed_cache_path)
-                    .await
-                {
-                    Some(cache) => Some(cache),
-                    _ => None,
-                }
+            if let Some(other) = names.insert(id.clone(), name.clone()) {
+                return Err(JiraError::Config(format!(
+                    "`{}` and `{}` both set the field {}",
+                    other, name, id
+                )));
             }
+            resolved.insert(id, value);
+        }
+        Ok(resolved)
+    }
+
+    /// Names of the custom fields of the create screen of `issue_type`, by id.
+    async fn issue_type_fields(
+        &self,
+        client: &JiraClient,
+        project: &str,
+        issue_type: Option<&str>,
+    ) -> JiraResult<Option<CustomFieldsCache>> {
+        let issue_type = match issue_type {
+            Some(issue_type) => issue_type,
+            None => return Ok(None),
+        };
+        let schemas = self
+            .get_or_cache_schemas(client, project, issue_type)
+            .await?;
+        Ok(Some(
+            schemas
+                .into_iter()
+                .filter(|(id, _)| id.starts_with(CUSTOM_FIELD_PREFIX))
+                .map(|(id, meta)| (id, meta.name))
+                .collect(),
+        ))
+    }
+
+    pub async fn get_or_cache(
+        &self,
+        client: &JiraClient,
+        project: &str,
+    ) -> JiraResult<(CustomFieldsCache, CustomFieldsCache)> {
+        let paths = cache_paths(client, project)?;
+        info!("Reversed cache path {}", &paths.reversed);
+        info!("Cache path {}", &paths.custom_fields);
+
+        let custom_fields = json_from_file::<CustomFieldsCache>(&paths.custom_fields).await;
+        let reversed_custom_fields = json_from_file::<CustomFieldsCache>(&paths.reversed).await;
+
+        match (custom_fields, reversed_custom_fields) {
+            (Ok(cf), Ok(rcf)) => Ok((cf, rcf)),
+            _ => self
+                .save_custom_fields(client, project, &paths)
+                .await
+                .map(|(cf, rcf, _)| (cf, rcf)),
         }
     }
+
+    /// Schemas and allowed values of the fields of the create screen of
+    /// `issue_type` in `project`, cached next to the custom field names.
+    pub async fn get_or_cache_schemas(
+        &self,
+        client: &JiraClient,
+        project: &str,
+        issue_type: &str,
+    ) -> JiraResult<FieldSchemas> {
+        let paths = cache_paths(client, project)?;
+        info!("Schemas cache path {}", &paths.schemas);
+
+        let schemas = match json_from_file::<IssueTypeSchemas>(&paths.schemas).await {
+            Ok(schemas) => schemas,
+            Err(_) => self.save_custom_fields(client, project, &paths).await?.2,
+        };
+        schemas
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(issue_type))
+            .map(|(_, fields)| fields)
+            .ok_or_else(|| {
+                JiraError::NotFound(format!("issue type {} of project {}", issue_type, project))
+            })
+    }
+}
+
+/// `~/.jira-cli/cache/<host>/<PROJECT>*.json`, the directory being created
+/// when missing. `https://jira.example.com/jira` is cached under
+/// `jira.example.com_jira`.
+fn cache_paths(client: &JiraClient, project: &str) -> JiraResult<CachePaths> {
+    let host = client.host();
+    let host = host.split_once("://").map_or(host, |(_, rest)| rest);
+    let host_dir = host
+        .trim_end_matches('/')
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() || c == '.' || c == '-' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+
+    let mut dir: PathBuf = home_dir()
+        .ok_or_else(|| JiraError::Config("could not find the home directory".to_string()))?;
+    dir.push(CACHE_DIR);
+    dir.push(host_dir);
+    fs::create_dir_all(&dir).map_err(|e| JiraError::io(&dir.to_string_lossy(), e))?;
+
+    let path = |suffix: &str| {
+        dir.join(format!("{}{}", project, suffix))
+            .to_string_lossy()
+            .to_string()
+    };
+    Ok(CachePaths {
+        custom_fields: path(".json"),
+        reversed: path(".reversed.json"),
+        schemas: path(".schemas.json"),
+    })
+}
+
+fn resolve_column(
+    name: &str,
+    custom_fields: &CustomFieldsCache,
+    project: Option<&str>,
+) -> JiraResult<Column> {
+    if let Some(id) = unique_field(custom_fields, name, true, project)? {
+        return Ok(Column::new(&custom_fields[&id], &id));
+    }
+    if is_field_id(name) {
+        let field_name = custom_fields
+            .get(name)
+            .map_or(name, |field_name| field_name);
+        return Ok(Column::new(field_name, name));
+    }
+
+    match project {
+        Some(project) => Err(JiraError::NotFound(format!(
+            "field `{}` in project {}",
+            name, project
+        ))),
+        None => Err(JiraError::Config(format!(
+            "`{}` is not a field id, pass --project to use custom field names",
+            name
+        ))),
+    }
+}
+
+/// Id of the custom field named `name`, `None` when there is none. Several
+/// custom fields sharing the name are an error.
+fn unique_field(
+    custom_fields: &CustomFieldsCache,
+    name: &str,
+    ignore_case: bool,
+    project: Option<&str>,
+) -> JiraResult<Option<String>> {
+    let mut ids = custom_fields
+        .iter()
+        .filter(|(_, field_name)| match ignore_case {
+            true => field_name.eq_ignore_ascii_case(name),
+            false => field_name.as_str() == name,
+        })
+        .map(|(id, _)| id.clone())
+        .collect::<Vec<String>>();
+    ids.sort();
+
+    match ids.as_slice() {
+        [] => Ok(None),
+        [id] => Ok(Some(id.clone())),
+        _ => Err(JiraError::Config(format!(
+            "`{}` names several custom fields{}: {}, use one of these ids instead",
+            name,
+            project
+                .map(|project| format!(" of project {}", project))
+                .unwrap_or_default(),
+            ids.join(", ")
+        ))),
+    }
+}
+
+/// Jira field ids look like `status`, `fixVersions` or `customfield_10016`.
+fn is_field_id(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
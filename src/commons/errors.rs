@@ -0,0 +1,176 @@
+use crate::commons::client::retry_after;
+use crate::commons::structs::ErrorCollection;
+use reqwest::{Response, StatusCode};
+use thiserror::Error;
+
+pub type JiraResult<T> = Result<T, JiraError>;
+
+/// Every failure a command can end with. Each variant maps to a documented
+/// process exit code, see `exit_code`.
+#[derive(Debug, Error)]
+pub enum JiraError {
+    #[error("configuration error: {0}")]
+    Config(String),
+
+    #[error("invalid URL: {0}")]
+    Url(#[from] url::ParseError),
+
+    #[error("{status} for {url}, check the credentials of the profile{}", join_messages(.messages))]
+    Auth {
+        status: StatusCode,
+        url: String,
+        messages: Vec<String>,
+    },
+
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("Jira rejected the request with {status}{}", join_messages(.messages))]
+    Jira {
+        status: StatusCode,
+        messages: Vec<String>,
+    },
+
+    #[error("{status} for {url}")]
+    Http { status: StatusCode, url: String },
+
+    /// 429 responses Jira still sends after the retries, or asking to wait
+    /// longer than `max_retry_delay`.
+    #[error("rate limited by Jira for {url}{}", retry_hint(.retry_after))]
+    RateLimited {
+        url: String,
+        /// Seconds the `Retry-After` header asks to wait.
+        retry_after: Option<u64>,
+    },
+
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("failed to parse {what}: {message}")]
+    Deserialize { what: String, message: String },
+
+    #[error("{succeeded} of {total} {what}, {} failed", .total - .succeeded)]
+    PartialFailure {
+        succeeded: usize,
+        total: usize,
+        what: &'static str,
+    },
+
+    #[error("{path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+impl JiraError {
+    /// Process exit code, documented in the README.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            JiraError::Config(_) | JiraError::Url(_) => 2,
+            JiraError::Auth { .. } => 3,
+            JiraError::NotFound(_) => 4,
+            JiraError::Jira { status, .. } if *status == StatusCode::NOT_FOUND => 4,
+            JiraError::Jira { .. } => 5,
+            JiraError::Http { .. } | JiraError::RateLimited { .. } | JiraError::Request(_) => 6,
+            JiraError::Deserialize { .. } => 7,
+            JiraError::Io { .. } => 8,
+            JiraError::PartialFailure { succeeded: 0, .. } => 5,
+            JiraError::PartialFailure { .. } => 9,
+        }
+    }
+
+    /// Turns a non successful response into an error, reading the
+    /// `errorMessages`/`errors` body Jira sends along with it.
+    pub async fn from_response(response: Response) -> JiraError {
+        let status = response.status();
+        let url = response.url().to_string();
+        let delay = retry_after(&response);
+        let body = response.text().await.unwrap_or_default();
+        match JiraError::from_status(status, url, &body) {
+            JiraError::RateLimited { url, .. } => JiraError::RateLimited {
+                url,
+                retry_after: delay.map(|delay| delay.as_secs()),
+            },
+            e => e,
+        }
+    }
+
+    pub fn from_status(status: StatusCode, url: String, body: &str) -> JiraError {
+        let messages = match serde_json::from_str::<ErrorCollection>(body) {
+            Ok(errors) => errors.messages(),
+            Err(_) => vec![],
+        };
+
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => JiraError::Auth {
+                status,
+                url,
+                messages,
+            },
+            StatusCode::TOO_MANY_REQUESTS => JiraError::RateLimited {
+                url,
+                retry_after: None,
+            },
+            StatusCode::NOT_FOUND => match messages.as_slice() {
+                [] => JiraError::NotFound(url),
+                _ => JiraError::Jira { status, messages },
+            },
+            _ if messages.is_empty() => JiraError::Http { status, url },
+            _ => JiraError::Jira { status, messages },
+        }
+    }
+
+    pub fn deserialize(what: &str, error: impl std::fmt::Display) -> JiraError {
+        JiraError::Deserialize {
+            what: what.to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    pub fn io(path: &str, source: std::io::Error) -> JiraError {
+        JiraError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+}
+
+fn retry_hint(retry_after: &Option<u64>) -> String {
+    match retry_after {
+        Some(seconds) => format!(", it asks to retry in {}s", seconds),
+        None => String::new(),
+    }
+}
+
+fn join_messages(messages: &[String]) -> String {
+    match messages {
+        [] => String::new(),
+        _ => format!(": {}", messages.join("; ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_many_requests_is_rate_limited() {
+        let e = JiraError::from_status(StatusCode::TOO_MANY_REQUESTS, "http://jira/x".into(), "");
+        assert!(matches!(e, JiraError::RateLimited { .. }));
+        assert_eq!(e.exit_code(), 6);
+        assert_eq!(e.to_string(), "rate limited by Jira for http://jira/x");
+    }
+
+    #[test]
+    fn rate_limited_shows_retry_after() {
+        let e = JiraError::RateLimited {
+            url: "http://jira/x".into(),
+            retry_after: Some(120),
+        };
+        assert_eq!(
+            e.to_string(),
+            "rate limited by Jira for http://jira/x, it asks to retry in 120s"
+        );
+    }
+}
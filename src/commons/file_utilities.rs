@@ -1,38 +1,29 @@
-extern crate yaml_rust;
-use anyhow::{anyhow, bail};
+use crate::commons::errors::{JiraError, JiraResult};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{BufReader, Read};
 
-pub async fn load_yaml(yaml_path: &str) -> Result<String, anyhow::Error> {
+pub async fn load_yaml(yaml_path: &str) -> JiraResult<String> {
     // Open stories yaml.
-    let mut yaml_file = File::open(yaml_path).unwrap();
+    let mut yaml_file = File::open(yaml_path).map_err(|e| JiraError::io(yaml_path, e))?;
 
     let mut yaml_as_string = String::new();
 
     match yaml_file.read_to_string(&mut yaml_as_string) {
         Ok(_) => Ok(yaml_as_string),
-        Err(_) => Err(anyhow!("failed to read yaml")),
+        Err(e) => Err(JiraError::io(yaml_path, e)),
     }
 }
 
-pub async fn json_to_file<T: Serialize>(payload: T, path: &str) -> serde_json::Result<()> {
-    let file = File::create(&path).unwrap();
+pub async fn json_to_file<T: Serialize>(payload: T, path: &str) -> JiraResult<()> {
+    let file = File::create(path).map_err(|e| JiraError::io(path, e))?;
 
-    serde_json::to_writer(file, &payload)
+    serde_json::to_writer(file, &payload).map_err(|e| JiraError::io(path, e.into()))
 }
 
-pub async fn json_from_file<T: for<'de> Deserialize<'de>>(
-    path: &str,
-) -> Result<serde_json::Result<T>, anyhow::Error> {
-    match File::open(&path) {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            match serde_json::from_reader::<BufReader<File>, T>(reader) {
-                Ok(json) => Ok(Ok(json)),
-                Err(e) => bail!(e),
-            }
-        }
-        Err(e) => bail!(e),
-    }
+pub async fn json_from_file<T: for<'de> Deserialize<'de>>(path: &str) -> JiraResult<T> {
+    let file = File::open(path).map_err(|e| JiraError::io(path, e))?;
+    let reader = BufReader::new(file);
+    serde_json::from_reader::<BufReader<File>, T>(reader)
+        .map_err(|e| JiraError::deserialize(path, e))
 }
@@ -1,4 +1,14 @@
+pub mod arrange;
+pub mod client;
+pub mod command_args;
+pub mod config;
 pub mod custom_fields;
+pub mod errors;
 pub mod file_utilities;
+pub mod output;
 pub(crate) mod req_builder;
+pub mod search;
 pub mod structs;
+pub mod table;
+pub mod template;
+pub mod terminal;
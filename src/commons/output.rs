@@ -0,0 +1,452 @@
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::structs::Issue;
+use crate::commons::table::{column_header, field_text, render_table, styled, Cell, TableLayout};
+use crate::commons::template::Template;
+use crate::commons::terminal::write_paged;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Default columns of `list stories` and `list epics`.
+pub static LIST_COLUMNS: [&str; 4] = ["summary", "key", "id", "link"];
+/// Default columns of `search`.
+pub static SEARCH_COLUMNS: [&str; 4] = ["key", "summary", "status", "assignee"];
+/// Columns read from the issue itself rather than from its fields.
+pub static ISSUE_ATTRIBUTES: [&str; 3] = ["key", "id", "link"];
+
+/// Format list commands write to stdout, selected with `--output`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+    Csv,
+    Tsv,
+}
+
+impl OutputFormat {
+    pub const VARIANTS: [&'static str; 5] = ["table", "json", "yaml", "csv", "tsv"];
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_ascii_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(format!(
+                "unknown output format {}, expected one of {}",
+                format,
+                OutputFormat::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Global rendering settings handed to every list command.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub format: OutputFormat,
+    /// The `columns` block of conf.yaml.
+    pub default_columns: BTreeMap<String, Vec<String>>,
+    /// `--format` template, replaces `format` when given.
+    pub template: Option<Template>,
+    pub layout: TableLayout,
+    /// Whether long output may go through `$PAGER`.
+    pub pager: bool,
+}
+
+impl RenderOptions {
+    /// Columns asked with `--columns`, else the ones read by `--format`, else
+    /// the conf.yaml default of `command`, else `builtin`.
+    pub fn columns(&self, command: &str, requested: &[String], builtin: &[&str]) -> Vec<String> {
+        if !requested.is_empty() {
+            return requested.to_vec();
+        }
+        if let Some(template) = &self.template {
+            return template.columns();
+        }
+        match self.default_columns.get(command) {
+            Some(columns) if !columns.is_empty() => columns.clone(),
+            _ => builtin.iter().map(|column| column.to_string()).collect(),
+        }
+    }
+}
+
+/// A rendered column: the name shown in headers and used as JSON/YAML key,
+/// and the issue field or attribute it reads.
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub field: String,
+}
+
+impl Column {
+    pub fn new(name: &str, field: &str) -> Self {
+        Column {
+            name: name.to_string(),
+            field: field.to_string(),
+        }
+    }
+
+    /// Whether the column is read from the issue fields, and so has to be
+    /// requested from the search endpoint.
+    pub fn is_field(&self) -> bool {
+        !ISSUE_ATTRIBUTES.contains(&self.field.as_str())
+    }
+}
+
+/// Rows printed by a list command. Cells keep their JSON value so JSON and
+/// YAML output stay structured, the other formats show their `field_text`.
+#[derive(Debug, Clone, Default)]
+pub struct Listing {
+    pub columns: Vec<String>,
+    /// Field or attribute read by each column.
+    pub fields: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// The object behind each row, for `--format` paths that are not a column.
+    pub sources: Vec<Value>,
+    pub footer: Option<String>,
+    /// Column rows are grouped by, see `arrange`.
+    pub group_by: Option<usize>,
+    /// Columns totalled below the rows.
+    pub sums: Vec<usize>,
+    /// Columns only read to sort or group the rows, left out of the output.
+    pub hidden: Vec<usize>,
+}
+
+impl Listing {
+    pub fn new(columns: &[&str]) -> Self {
+        let columns: Vec<String> = columns.iter().map(|column| column.to_string()).collect();
+        Listing {
+            fields: columns.clone(),
+            columns,
+            ..Default::default()
+        }
+    }
+
+    /// One row per issue.
+    pub fn from_issues(issues: &[Issue], columns: &[Column]) -> Self {
+        let mut listing = Listing {
+            columns: columns.iter().map(|column| column.name.clone()).collect(),
+            fields: columns.iter().map(|column| column.field.clone()).collect(),
+            ..Default::default()
+        };
+        for issue in issues {
+            listing.rows.push(
+                columns
+                    .iter()
+                    .map(|column| issue_value(issue, &column.field))
+                    .collect(),
+            );
+            listing
+                .sources
+                .push(serde_json::to_value(issue).unwrap_or_default());
+        }
+        listing
+    }
+
+    pub fn footer(mut self, footer: String) -> Self {
+        self.footer = Some(footer);
+        self
+    }
+
+    /// Writes the rows to stdout. The footer is only part of the table output,
+    /// the other formats log it so their stdout stays machine readable.
+    pub fn print(&self, options: &RenderOptions) -> JiraResult<()> {
+        let mut text = match &options.template {
+            Some(template) => self.render_template(template),
+            None => self.render(options)?,
+        };
+        match (&self.footer, options.format) {
+            (Some(footer), OutputFormat::Table) if options.template.is_none() => {
+                text.push_str(footer);
+                text.push('\n');
+            }
+            (Some(footer), _) => info!("{}", footer),
+            (None, _) => {}
+        }
+        write_paged(&text, options.pager).map_err(|e| JiraError::io("stdout", e))
+    }
+
+    pub fn render(&self, options: &RenderOptions) -> JiraResult<String> {
+        match options.format {
+            OutputFormat::Table => Ok(self.render_tables(options.layout)),
+            OutputFormat::Json | OutputFormat::Yaml => self.render_structured(options.format),
+            OutputFormat::Csv => Ok(self.delimited(',', csv_cell)),
+            OutputFormat::Tsv => Ok(self.delimited('\t', tsv_cell)),
+        }
+    }
+
+    /// One line per row. A path starts at a column, matched by name, else at
+    /// the row source or its `fields`.
+    pub fn render_template(&self, template: &Template) -> String {
+        let empty = Value::Null;
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let source = self.sources.get(index).unwrap_or(&empty);
+                template.render(|path| {
+                    let (root, rest) = path.split_first()?;
+                    match self.column_index(root) {
+                        Some(column) => value_at(&row[column], rest),
+                        None => {
+                            value_at(source, path).or_else(|| value_at(source.get("fields")?, path))
+                        }
+                    }
+                }) + "\n"
+            })
+            .collect()
+    }
+
+    /// A table per group, each headed by its name and count, ending with a
+    /// totals row when columns are summed.
+    fn render_tables(&self, layout: TableLayout) -> String {
+        if self.group_by.is_none() {
+            return self.render_table(0..self.rows.len(), layout);
+        }
+        let mut tables: String = self
+            .groups()
+            .into_iter()
+            .map(|(group, rows)| {
+                let heading = Cell::bold(format!("{} ({})", group, rows.len()));
+                format!(
+                    "{}\n{}",
+                    styled(&heading, layout),
+                    self.render_table(rows, layout)
+                )
+            })
+            .collect();
+        if !self.sums.is_empty() {
+            let totals = self.totals(0..self.rows.len());
+            let totals = totals
+                .columns
+                .iter()
+                .zip(&totals.values)
+                .map(|(column, value)| format!("{} {}", column, field_text(value)))
+                .collect::<Vec<String>>();
+            tables.push_str(&format!("Total: {}\n", totals.join(", ")));
+        }
+        tables
+    }
+
+    fn render_table(&self, rows: Range<usize>, layout: TableLayout) -> String {
+        let mut cells = self.rows[rows.clone()]
+            .iter()
+            .map(|row| {
+                let cells = row
+                    .iter()
+                    .zip(&self.fields)
+                    .map(|(value, field)| Cell::field(field, value))
+                    .collect::<Vec<Cell>>();
+                self.shown(&cells)
+            })
+            .collect::<Vec<Vec<Cell>>>();
+        if !self.sums.is_empty() {
+            cells.push(
+                self.totals_text(rows, None)
+                    .into_iter()
+                    .map(Cell::bold)
+                    .collect(),
+            );
+        }
+        render_table(&self.headers(), &cells, layout)
+    }
+
+    /// A list of rows, or an object holding them along with their totals, or
+    /// a list of such objects when grouping.
+    fn render_structured(&self, format: OutputFormat) -> JiraResult<String> {
+        let all = 0..self.rows.len();
+        match (self.group_by, self.sums.is_empty()) {
+            (None, true) => serialize(&self.records(all), format),
+            (None, false) => serialize(&self.summary(None, all), format),
+            (Some(_), _) => {
+                let groups = self
+                    .groups()
+                    .into_iter()
+                    .map(|(group, rows)| self.summary(Some(group), rows))
+                    .collect::<Vec<Summary>>();
+                serialize(&groups, format)
+            }
+        }
+    }
+
+    fn summary(&self, group: Option<String>, rows: Range<usize>) -> Summary {
+        Summary {
+            group,
+            count: rows.len(),
+            totals: match self.sums.is_empty() {
+                true => None,
+                false => Some(self.totals(rows.clone())),
+            },
+            issues: self.records(rows),
+        }
+    }
+
+    /// The values of `row` in the columns that are not hidden.
+    pub(crate) fn shown<T: Clone>(&self, row: &[T]) -> Vec<T> {
+        row.iter()
+            .enumerate()
+            .filter(|(column, _)| !self.hidden.contains(column))
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    fn headers(&self) -> Vec<String> {
+        self.shown(&self.columns)
+            .iter()
+            .map(|column| column_header(column))
+            .collect()
+    }
+
+    fn text_rows(&self, rows: Range<usize>) -> Vec<Vec<String>> {
+        self.rows[rows]
+            .iter()
+            .map(|row| self.shown(row).iter().map(field_text).collect())
+            .collect()
+    }
+
+    fn records(&self, rows: Range<usize>) -> Vec<Record> {
+        self.rows[rows]
+            .iter()
+            .map(|row| Record {
+                columns: self.shown(&self.columns),
+                values: self.shown(row),
+            })
+            .collect()
+    }
+
+    /// A header line and a line per row, rows of a group following each other.
+    /// With `--sum`, each group ends with a totals line holding its name in the
+    /// group column, and a grand totals line comes last.
+    fn delimited(&self, delimiter: char, cell: fn(&str) -> String) -> String {
+        let separator = delimiter.to_string();
+        let all = 0..self.rows.len();
+        let mut lines = vec![self.shown(&self.columns)];
+        match (self.group_by, self.sums.is_empty()) {
+            (_, true) => lines.extend(self.text_rows(all)),
+            (None, false) => {
+                lines.extend(self.text_rows(all.clone()));
+                lines.push(self.totals_text(all, None));
+            }
+            (Some(_), false) => {
+                for (group, rows) in self.groups() {
+                    lines.extend(self.text_rows(rows.clone()));
+                    lines.push(self.totals_text(rows, Some(&group)));
+                }
+                lines.push(self.totals_text(all, None));
+            }
+        }
+
+        lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|text| cell(text))
+                    .collect::<Vec<String>>()
+                    .join(&separator)
+                    + "\n"
+            })
+            .collect()
+    }
+}
+
+/// One row serialized as a map whose keys keep the column order.
+pub(crate) struct Record {
+    pub(crate) columns: Vec<String>,
+    pub(crate) values: Vec<Value>,
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(&self.values) {
+            map.serialize_entry(column, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Value of the `column` of an issue. Besides field ids, `key`, `id` and
+/// `link` select the matching issue attributes.
+pub fn issue_value(issue: &Issue, column: &str) -> Value {
+    match column {
+        "key" => Value::String(issue.key.clone()),
+        "id" => Value::String(issue.id.clone()),
+        "link" => Value::String(issue.issue_link.clone()),
+        field => issue
+            .fields
+            .as_ref()
+            .and_then(|fields| fields.get(field))
+            .unwrap_or(Value::Null),
+    }
+}
+
+/// Rows of a group, or of the whole listing, along with their totals.
+#[derive(Serialize)]
+struct Summary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    count: usize,
+    issues: Vec<Record>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    totals: Option<Record>,
+}
+
+pub fn serialize<T: Serialize>(value: &T, format: OutputFormat) -> JiraResult<String> {
+    match format {
+        OutputFormat::Yaml => {
+            serde_yaml::to_string(value).map_err(|e| JiraError::deserialize("the YAML output", e))
+        }
+        _ => serde_json::to_string_pretty(value)
+            .map(|json| json + "\n")
+            .map_err(|e| JiraError::deserialize("the JSON output", e)),
+    }
+}
+
+/// Follows a dotted path through objects and array indexes.
+fn value_at(value: &Value, path: &[String]) -> Option<Value> {
+    path.iter()
+        .try_fold(value, |value, segment| match value {
+            Value::Array(values) => values.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        })
+        .filter(|value| !value.is_null())
+        .cloned()
+}
+
+/// Quotes a CSV cell holding a comma, a quote or a line break (RFC 4180).
+fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// TSV has no quoting, tabs and line breaks inside a cell become spaces.
+fn tsv_cell(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
@@ -1,26 +1,34 @@
-use crate::commons::structs::AuthOptions;
+use crate::commons::client::JiraClient;
+use crate::commons::structs::AuthMethod;
 use reqwest::header::CONTENT_TYPE;
 use reqwest::RequestBuilder;
 use reqwest::Url;
 
-pub fn build_get_req(uri: Url, auth_options: &AuthOptions) -> RequestBuilder {
-    let client = reqwest::Client::new();
-    client
-        .get(uri)
-        .basic_auth(
-            auth_options.user.as_ref().unwrap(),
-            auth_options.pass.clone(),
-        )
+pub fn build_get_req(uri: Url, client: &JiraClient) -> RequestBuilder {
+    with_auth(client.http.get(uri), &client.auth_options.auth)
         .header(CONTENT_TYPE, "application/json")
 }
 
-pub fn build_post_req(uri: Url, auth_options: &AuthOptions) -> RequestBuilder {
-    let client = reqwest::Client::new();
-    client
-        .post(uri)
-        .basic_auth(
-            auth_options.user.as_ref().unwrap(),
-            auth_options.pass.clone(),
-        )
+pub fn build_post_req(uri: Url, client: &JiraClient) -> RequestBuilder {
+    with_auth(client.http.post(uri), &client.auth_options.auth)
         .header(CONTENT_TYPE, "application/json")
 }
+
+pub fn build_put_req(uri: Url, client: &JiraClient) -> RequestBuilder {
+    with_auth(client.http.put(uri), &client.auth_options.auth)
+        .header(CONTENT_TYPE, "application/json")
+}
+
+pub fn build_delete_req(uri: Url, client: &JiraClient) -> RequestBuilder {
+    with_auth(client.http.delete(uri), &client.auth_options.auth)
+        .header(CONTENT_TYPE, "application/json")
+}
+
+fn with_auth(req: RequestBuilder, auth: &AuthMethod) -> RequestBuilder {
+    match auth {
+        AuthMethod::Basic { user, password } => req.basic_auth(user, Some(password)),
+        AuthMethod::ApiToken { email, token } => req.basic_auth(email, Some(token)),
+        AuthMethod::Bearer { token } => req.bearer_auth(token),
+        AuthMethod::Anonymous => req,
+    }
+}
@@ -0,0 +1,115 @@
+use crate::commons::client::JiraClient;
+use crate::commons::errors::JiraResult;
+use crate::commons::output::Column;
+use crate::commons::req_builder::build_get_req;
+use crate::commons::structs::{Issue, SearchResponse, REST_URI, SEARCH_URI};
+use url::Url;
+
+static PAGE_SIZE: usize = 100;
+
+/// Pages through the results of a JQL search, following `startAt`/`total`
+/// until every issue, or `limit` issues, have been fetched.
+pub struct IssueSearch<'a> {
+    client: &'a JiraClient,
+    jql: String,
+    fields: Vec<String>,
+    limit: Option<usize>,
+    start_at: usize,
+    total: Option<usize>,
+}
+
+/// Issues fetched by a search along with the total Jira reported for it.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub issues: Vec<Issue>,
+    pub total: usize,
+}
+
+impl<'a> IssueSearch<'a> {
+    pub fn new(client: &'a JiraClient, jql: &str) -> Self {
+        IssueSearch {
+            client,
+            jql: jql.to_string(),
+            fields: vec![],
+            limit: None,
+            start_at: 0,
+            total: None,
+        }
+    }
+
+    /// Fields read by `columns`, `summary` alone when they only show issue
+    /// attributes since an empty list would return every field.
+    pub fn columns(mut self, columns: &[Column]) -> Self {
+        self.fields = columns
+            .iter()
+            .filter(|column| column.is_field())
+            .map(|column| column.field.clone())
+            .collect();
+        if self.fields.is_empty() {
+            self.fields.push("summary".to_string());
+        }
+        self
+    }
+
+    /// Stops after `limit` issues.
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Fetches the next page, `None` once every issue was returned.
+    pub async fn next_page(&mut self) -> JiraResult<Option<Vec<Issue>>> {
+        let remaining = match (self.total, self.limit) {
+            (Some(total), _) if self.start_at >= total => return Ok(None),
+            (_, Some(limit)) if self.start_at >= limit => return Ok(None),
+            (_, Some(limit)) => (limit - self.start_at).min(PAGE_SIZE),
+            (_, None) => PAGE_SIZE,
+        };
+
+        let mut url = Url::parse(&format!("{}{}{}", self.client.host(), REST_URI, SEARCH_URI))?;
+        url.query_pairs_mut()
+            .append_pair("jql", &self.jql)
+            .append_pair("startAt", &self.start_at.to_string())
+            .append_pair("maxResults", &remaining.to_string());
+        if !self.fields.is_empty() {
+            url.query_pairs_mut()
+                .append_pair("fields", &self.fields.join(","));
+        }
+        debug!("Search request {}", url);
+
+        let page = self
+            .client
+            .send_json::<SearchResponse>(build_get_req(url, self.client))
+            .await?;
+        let issues = page.issues.unwrap_or_default();
+
+        self.total = page.total;
+        self.start_at += issues.len();
+
+        if issues.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(issues))
+        }
+    }
+
+    /// Fetches every page.
+    pub async fn collect(mut self) -> JiraResult<SearchResult> {
+        let mut issues = vec![];
+        while let Some(page) = self.next_page().await? {
+            issues.extend(page);
+        }
+
+        Ok(SearchResult {
+            total: self.total.unwrap_or(issues.len()),
+            issues,
+        })
+    }
+}
+
+impl SearchResult {
+    /// Footer printed below list output.
+    pub fn footer(&self) -> String {
+        format!("Showing {} of {}", self.issues.len(), self.total)
+    }
+}
@@ -3,26 +3,41 @@ use serde_json::Value;
 use std::collections::HashMap;
 
 pub static REST_URI: &str = "/rest/api/2";
-pub static JQL: &str = "/search?jql=";
+pub static SEARCH_URI: &str = "/search";
+/// Argument standing for stdin where a file or value is expected.
+pub static STDIN: &str = "-";
+/// Format of the dates and times shown by `issue view` and comment lists.
+pub static DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct AuthOptions {
     pub host: String,
-    pub user: Option<String>,
-    pub pass: Option<String>,
+    pub auth: AuthMethod,
+}
+
+/// How requests authenticate against Jira, selected per profile with the `auth` key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthMethod {
+    /// Username and password sent as HTTP basic auth.
+    Basic { user: String, password: String },
+    /// Jira Cloud account email and API token, sent as HTTP basic auth.
+    ApiToken { email: String, token: String },
+    /// Server/Data Center personal access token sent as `Authorization: Bearer`.
+    Bearer { token: String },
+    /// No credentials at all, for instances allowing anonymous access.
+    Anonymous,
 }
 
 impl Default for AuthOptions {
     fn default() -> Self {
         AuthOptions {
             host: "localhost".to_string(),
-            user: None,
-            pass: None,
+            auth: AuthMethod::Anonymous,
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Issue {
     pub expand: String,
     pub id: String,
@@ -32,10 +47,44 @@ pub struct Issue {
     pub fields: Option<Fields>,
 }
 
+/// Project of an issue key, `JC` for `JC-42`. Project keys may hold `-` on
+/// some instances, issue numbers never do.
+pub fn project_key(issue_key: &str) -> &str {
+    issue_key
+        .rsplit_once('-')
+        .map_or(issue_key, |(project, _)| project)
+}
+
+/// Cloud account ids are 24 hexadecimal digits or `557058:` followed by a
+/// UUID, server user names being neither.
+pub fn is_account_id(text: &str) -> bool {
+    text.contains(':') || (text.len() == 24 && text.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// One page of a `/search` response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchResponse {
+    pub total: Option<usize>,
+    pub issues: Option<Vec<Issue>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fields {
     pub summary: Option<String>,
     pub description: Option<String>,
+    #[serde(flatten)]
+    pub unmapped_fields: HashMap<String, Value>,
+}
+
+impl Fields {
+    /// Value of the field with id `field`, e.g. `status` or `customfield_10014`.
+    pub fn get(&self, field: &str) -> Option<Value> {
+        match field {
+            "summary" => self.summary.clone().map(Value::String),
+            "description" => self.description.clone().map(Value::String),
+            _ => self.unmapped_fields.get(field).cloned(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,12 +99,27 @@ pub struct IssueType {
     pub fields: Option<FieldsType>,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct Component {
-    pub name: String,
+/// Error body Jira sends along with failed requests.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ErrorCollection {
+    #[serde(rename = "errorMessages", default)]
+    pub error_messages: Vec<String>,
+    #[serde(default)]
+    pub errors: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct IssuesResponse {
-    pub issues: Vec<Value>,
+impl ErrorCollection {
+    /// General messages first, then one `field: message` entry per field error.
+    pub fn messages(&self) -> Vec<String> {
+        let mut field_errors: Vec<String> = self
+            .errors
+            .iter()
+            .map(|(field, message)| format!("{}: {}", field, message))
+            .collect();
+        field_errors.sort();
+
+        let mut messages = self.error_messages.clone();
+        messages.extend(field_errors);
+        messages
+    }
 }
@@ -0,0 +1,440 @@
+use serde_json::Value;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+static MAX_COLUMN_WIDTH: usize = 80;
+/// Narrowest a column is shrunk to when fitting the terminal.
+static MIN_COLUMN_WIDTH: usize = 6;
+static ELLIPSIS: char = '…';
+static INDENT: &str = "  ";
+static GAP: &str = "   ";
+
+static RESET: &str = "\x1b[0m";
+static BOLD: &str = "\x1b[1m";
+static BOLD_RED: &str = "\x1b[1;31m";
+static RED: &str = "\x1b[31m";
+static GREEN: &str = "\x1b[32m";
+static YELLOW: &str = "\x1b[33m";
+static BLUE: &str = "\x1b[34m";
+static CYAN: &str = "\x1b[36m";
+
+/// Layout settings of a rendered table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableLayout {
+    /// Terminal width the table is fitted to, unknown when not on a terminal.
+    pub width: Option<usize>,
+    pub color: bool,
+}
+
+/// Text of a cell with the color it is shown with.
+#[derive(Debug, Clone)]
+pub struct Cell {
+    pub text: String,
+    pub color: Option<&'static str>,
+}
+
+impl Cell {
+    /// A field value, colored by status category or priority.
+    pub fn field(field: &str, value: &Value) -> Self {
+        Cell {
+            text: field_text(value),
+            color: value_color(field, value),
+        }
+    }
+
+    pub fn bold(text: String) -> Self {
+        Cell {
+            text,
+            color: Some(BOLD),
+        }
+    }
+}
+
+/// Renders a header row followed by one row per entry of `rows`.
+///
+/// On a terminal, columns are shrunk so the table fits its width, the widest
+/// first, and cut cells end with an ellipsis. Elsewhere cells wrap at
+/// `MAX_COLUMN_WIDTH`.
+pub fn render_table(headers: &[String], rows: &[Vec<Cell>], layout: TableLayout) -> String {
+    let headers = headers
+        .iter()
+        .map(|header| Cell::bold(header.clone()))
+        .collect::<Vec<Cell>>();
+    let natural = natural_widths(&headers, rows);
+    let widths = match layout.width {
+        Some(width) => fit_widths(natural, width),
+        None => natural
+            .into_iter()
+            .map(|width| width.min(MAX_COLUMN_WIDTH))
+            .collect(),
+    };
+
+    let mut table = String::new();
+    for row in std::iter::once(&headers).chain(rows) {
+        let lines = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| match layout.width {
+                Some(_) => vec![truncate(&cell.text, *width)],
+                None => wrap(&cell.text, *width),
+            })
+            .collect::<Vec<Vec<String>>>();
+        let height = lines.iter().map(Vec::len).max().unwrap_or(0);
+
+        for line in 0..height {
+            let mut text = INDENT.to_string();
+            for (column, cell) in row.iter().enumerate() {
+                let part = lines[column].get(line).cloned().unwrap_or_default();
+                let padding = widths[column].saturating_sub(part.width());
+                let part = Cell {
+                    text: part,
+                    color: cell.color,
+                };
+                text.push_str(&styled(&part, layout));
+                text.push_str(&" ".repeat(padding));
+                text.push_str(GAP);
+            }
+            table.push_str(text.trim_end());
+            table.push('\n');
+        }
+    }
+    table
+}
+
+/// Widest line of each column.
+fn natural_widths(headers: &[Cell], rows: &[Vec<Cell>]) -> Vec<usize> {
+    let mut widths = headers
+        .iter()
+        .map(|header| header.text.width())
+        .collect::<Vec<usize>>();
+    for row in rows {
+        for (column, cell) in row.iter().enumerate() {
+            let cell_width = cell
+                .text
+                .lines()
+                .map(|line| line.width())
+                .max()
+                .unwrap_or(0);
+            widths[column] = widths[column].max(cell_width);
+        }
+    }
+    widths
+}
+
+/// Width of each column so a row fits `width` terminal columns. Columns
+/// narrower than an even share of the space left keep their width.
+fn fit_widths(mut widths: Vec<usize>, width: usize) -> Vec<usize> {
+    let available = width.saturating_sub(INDENT.len() + GAP.len() * widths.len().saturating_sub(1));
+    if widths.iter().sum::<usize>() <= available {
+        return widths;
+    }
+
+    let mut fixed = vec![false; widths.len()];
+    loop {
+        let flexible = fixed.iter().filter(|fixed| !**fixed).count();
+        if flexible == 0 {
+            break;
+        }
+        let used: usize = (0..widths.len())
+            .filter(|column| fixed[*column])
+            .map(|column| widths[column])
+            .sum();
+        let share = (available.saturating_sub(used) / flexible).max(MIN_COLUMN_WIDTH);
+        let narrow = (0..widths.len())
+            .filter(|column| !fixed[*column] && widths[*column] <= share)
+            .collect::<Vec<usize>>();
+        if narrow.is_empty() {
+            for column in 0..widths.len() {
+                if !fixed[column] {
+                    widths[column] = share;
+                }
+            }
+            break;
+        }
+        for column in narrow {
+            fixed[column] = true;
+        }
+    }
+    widths
+}
+
+/// Splits `text` into lines of at most `width` terminal columns.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for line in text.lines() {
+        let mut current = String::new();
+        let mut used = 0;
+        for c in line.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if used + char_width > width && !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+                used = 0;
+            }
+            current.push(c);
+            used += char_width;
+        }
+        lines.push(current);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+/// Cuts `text` to `width` terminal columns, ending it with an ellipsis.
+fn truncate(text: &str, width: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.width() <= width && !text.contains('\n') {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in line.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+    truncated.push(ELLIPSIS);
+    truncated
+}
+
+/// Color of a status by its category, or of a priority by its name.
+fn value_color(field: &str, value: &Value) -> Option<&'static str> {
+    if let Some(category) = value.pointer("/statusCategory/key").and_then(Value::as_str) {
+        return match category {
+            "new" => Some(BLUE),
+            "indeterminate" => Some(YELLOW),
+            "done" => Some(GREEN),
+            _ => None,
+        };
+    }
+    if field != "priority" {
+        return None;
+    }
+    match field_text(value).to_ascii_lowercase().as_str() {
+        "highest" | "blocker" | "critical" => Some(BOLD_RED),
+        "high" | "major" => Some(RED),
+        "medium" => Some(YELLOW),
+        "low" | "minor" => Some(GREEN),
+        "lowest" | "trivial" => Some(CYAN),
+        _ => None,
+    }
+}
+
+/// Readable text of a field value: the name of statuses, users and options,
+/// comma separated lists and plain scalars.
+pub fn field_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Array(values) => values
+            .iter()
+            .map(field_text)
+            .collect::<Vec<String>>()
+            .join(", "),
+        Value::Object(object) => ["displayName", "name", "value", "key"]
+            .iter()
+            .find_map(|key| object.get(*key))
+            .map(field_text)
+            .unwrap_or_else(|| value.to_string()),
+    }
+}
+
+/// Header shown for a column id, `summary` becomes `Summary`.
+pub fn column_header(column: &str) -> String {
+    match column {
+        "id" => "ID".to_string(),
+        _ => {
+            let mut chars = column.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+    }
+}
+
+/// Text of a cell wrapped in its color when colors are enabled.
+pub fn styled(cell: &Cell, layout: TableLayout) -> String {
+    match cell.color {
+        Some(code) if layout.color && !cell.text.is_empty() => {
+            format!("{}{}{}", code, cell.text, RESET)
+        }
+        _ => cell.text.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn plain(width: Option<usize>) -> TableLayout {
+        TableLayout {
+            width,
+            color: false,
+        }
+    }
+
+    fn cells(texts: &[&str]) -> Vec<Cell> {
+        texts
+            .iter()
+            .map(|text| Cell {
+                text: text.to_string(),
+                color: None,
+            })
+            .collect()
+    }
+
+    fn headers(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    #[test]
+    fn fit_widths_keeps_fitting_columns() {
+        assert_eq!(fit_widths(vec![3, 10, 5], 80), vec![3, 10, 5]);
+    }
+
+    #[test]
+    fn fit_widths_shrinks_widest_columns() {
+        // 40 columns less the indent and two gaps leave 32.
+        let widths = fit_widths(vec![3, 50, 10], 40);
+        assert_eq!(widths, vec![3, 19, 10]);
+        assert_eq!(widths.iter().sum::<usize>(), 32);
+
+        assert_eq!(fit_widths(vec![40, 40], 40), vec![17, 17]);
+    }
+
+    #[test]
+    fn fit_widths_never_goes_below_the_minimum() {
+        assert_eq!(fit_widths(vec![10, 20], 0), vec![6, 6]);
+        assert_eq!(fit_widths(vec![2, 20], 5), vec![2, 6]);
+    }
+
+    #[test]
+    fn truncate_adds_an_ellipsis() {
+        assert_eq!(truncate("Export reports", 20), "Export reports");
+        assert_eq!(truncate("Export reports", 7), "Export…");
+        assert_eq!(truncate("Export\nreports", 20), "Export…");
+        assert_eq!(truncate("Export", 1), "…");
+    }
+
+    #[test]
+    fn truncate_to_zero_width() {
+        assert_eq!(truncate("Export", 0), "");
+        assert_eq!(truncate("", 0), "");
+    }
+
+    #[test]
+    fn truncate_counts_wide_characters_twice() {
+        assert_eq!(truncate("日本語テキスト", 5), "日本…");
+        assert_eq!(truncate("日本語テキスト", 6), "日本…");
+        assert_eq!(truncate("日本語", 6), "日本語");
+        assert_eq!(truncate("日本…", 5), "日本…");
+    }
+
+    #[test]
+    fn wrap_counts_wide_characters_twice() {
+        assert_eq!(wrap("日本語", 4), vec!["日本", "語"]);
+        assert_eq!(wrap("abcdef", 4), vec!["abcd", "ef"]);
+        assert_eq!(wrap("ab\ncd", 4), vec!["ab", "cd"]);
+        assert_eq!(wrap("", 4), vec![""]);
+        // A character wider than the column still takes a line of its own.
+        assert_eq!(wrap("日本", 1), vec!["日", "本"]);
+    }
+
+    #[test]
+    fn render_table_aligns_wide_characters() {
+        let rows = vec![cells(&["JC-1", "日本語"]), cells(&["JC-22", "abc"])];
+        let table = render_table(&headers(&["Key", "Summary"]), &rows, plain(None));
+        assert_eq!(
+            table,
+            "  Key     Summary\n  JC-1    日本語\n  JC-22   abc\n"
+        );
+    }
+
+    #[test]
+    fn render_table_fits_the_terminal_width() {
+        let rows = vec![
+            cells(&[
+                "JC-1",
+                "A summary much longer than the terminal is wide",
+                "Done",
+            ]),
+            cells(&["JC-2", "日本語のとても長い要約テキストです", "To Do"]),
+        ];
+        let table = render_table(
+            &headers(&["Key", "Summary", "Status"]),
+            &rows,
+            plain(Some(30)),
+        );
+        for line in table.lines() {
+            assert!(line.width() <= 30, "`{}` is wider than 30", line);
+        }
+        assert!(table.contains('…'));
+    }
+
+    #[test]
+    fn render_table_on_a_zero_width_terminal() {
+        let rows = vec![cells(&["JC-1", "Summary"])];
+        let table = render_table(&headers(&["Key", "Summary"]), &rows, plain(Some(0)));
+        assert_eq!(table, "  Key    Summa…\n  JC-1   Summa…\n");
+    }
+
+    #[test]
+    fn render_table_wraps_off_a_terminal() {
+        let long = "x".repeat(MAX_COLUMN_WIDTH + 5);
+        let rows = vec![cells(&["JC-1", &long])];
+        let table = render_table(&headers(&["Key", "Summary"]), &rows, plain(None));
+        assert_eq!(table.lines().count(), 3);
+        assert!(table
+            .lines()
+            .all(|line| line.width() <= 2 + 4 + 3 + MAX_COLUMN_WIDTH));
+    }
+
+    #[test]
+    fn styled_only_colors_when_enabled() {
+        let cell = Cell::bold("Key".to_string());
+        assert_eq!(styled(&cell, plain(None)), "Key");
+        let colored = TableLayout {
+            width: None,
+            color: true,
+        };
+        assert_eq!(styled(&cell, colored), "\x1b[1mKey\x1b[0m");
+        assert_eq!(styled(&Cell::bold(String::new()), colored), "");
+    }
+
+    #[test]
+    fn cells_colored_by_status_category_and_priority() {
+        let status = json!({"name": "Done", "statusCategory": {"key": "done"}});
+        assert_eq!(Cell::field("status", &status).color, Some(GREEN));
+        assert_eq!(
+            Cell::field("priority", &json!({"name": "High"})).color,
+            Some(RED)
+        );
+        assert_eq!(Cell::field("summary", &json!("High")).color, None);
+    }
+
+    #[test]
+    fn field_text_of_values() {
+        assert_eq!(field_text(&json!(null)), "");
+        assert_eq!(field_text(&json!(3.5)), "3.5");
+        assert_eq!(
+            field_text(&json!({"displayName": "Ana", "name": "ana"})),
+            "Ana"
+        );
+        assert_eq!(
+            field_text(&json!([{"name": "api"}, {"value": "web"}])),
+            "api, web"
+        );
+        assert_eq!(field_text(&json!({"other": 1})), "{\"other\":1}");
+    }
+}
@@ -0,0 +1,346 @@
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::table::field_text;
+use serde_json::Value;
+
+/// A `--format` template such as `{key}\t{status|upper}\t{summary|truncate:40}`.
+///
+/// Placeholders hold a dotted path, e.g. `fields.assignee.displayName` or a
+/// custom field name, followed by `|` separated filters. `{{` and `}}` print
+/// literal braces, `\t`, `\n` and `\\` are unescaped.
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone)]
+struct Placeholder {
+    path: Vec<String>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Truncate(usize),
+    Upper,
+    Lower,
+    Date(String),
+    Default(String),
+}
+
+static DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+impl Template {
+    pub fn parse(template: &str) -> JiraResult<Template> {
+        let invalid = |reason: &str| {
+            JiraError::Config(format!("invalid --format `{}`: {}", template, reason))
+        };
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(invalid("unmatched `}`, use `}}` for a literal brace")),
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(invalid("unclosed `{`")),
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Placeholder(
+                        Placeholder::parse(&placeholder).map_err(|e| invalid(&e))?,
+                    ));
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Fields the placeholders read, `fields.assignee.displayName` reads `assignee`.
+    pub fn columns(&self) -> Vec<String> {
+        let mut columns: Vec<String> = vec![];
+        for part in &self.parts {
+            if let Part::Placeholder(placeholder) = part {
+                let column = match placeholder.path.as_slice() {
+                    [root, field, ..] if root == "fields" => field,
+                    [root, ..] => root,
+                    [] => continue,
+                };
+                if !columns.contains(column) {
+                    columns.push(column.clone());
+                }
+            }
+        }
+        columns
+    }
+
+    /// Renders one line, `lookup` returning the value found at a path.
+    pub fn render(&self, lookup: impl Fn(&[String]) -> Option<Value>) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(placeholder) => {
+                    let text = lookup(&placeholder.path)
+                        .map(|value| field_text(&value))
+                        .unwrap_or_default();
+                    placeholder
+                        .filters
+                        .iter()
+                        .fold(text, |text, filter| filter.apply(text))
+                }
+            })
+            .collect()
+    }
+}
+
+impl Placeholder {
+    fn parse(placeholder: &str) -> Result<Placeholder, String> {
+        let mut sections = placeholder.split('|');
+        let path = sections.next().unwrap_or_default().trim();
+        if path.is_empty() {
+            return Err("empty placeholder".to_string());
+        }
+
+        Ok(Placeholder {
+            path: path
+                .split('.')
+                .map(|segment| segment.trim().to_string())
+                .collect(),
+            filters: sections.map(Filter::parse).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl Filter {
+    fn parse(filter: &str) -> Result<Filter, String> {
+        let (name, argument) = match filter.find(':') {
+            Some(index) => (&filter[..index], Some(&filter[index + 1..])),
+            None => (filter, None),
+        };
+
+        match (name.trim(), argument) {
+            ("upper", None) => Ok(Filter::Upper),
+            ("lower", None) => Ok(Filter::Lower),
+            ("truncate", Some(width)) => width
+                .trim()
+                .parse()
+                .map(Filter::Truncate)
+                .map_err(|_| format!("truncate expects a width, got `{}`", width)),
+            ("date", format) => Ok(Filter::Date(
+                format.unwrap_or(DEFAULT_DATE_FORMAT).to_string(),
+            )),
+            ("default", Some(default)) => Ok(Filter::Default(default.to_string())),
+            (name, _) => Err(format!(
+                "unknown filter `{}`, expected upper, lower, truncate:N, date[:FORMAT] or default:VALUE",
+                name
+            )),
+        }
+    }
+
+    fn apply(&self, text: String) -> String {
+        match self {
+            Filter::Truncate(width) => text.chars().take(*width).collect(),
+            Filter::Upper => text.to_uppercase(),
+            Filter::Lower => text.to_lowercase(),
+            Filter::Date(format) => format_date(&text, format).unwrap_or(text),
+            Filter::Default(default) if text.is_empty() => default.clone(),
+            Filter::Default(_) => text,
+        }
+    }
+}
+
+/// Formats a Jira timestamp, `2021-01-31T17:05:42.000+0000` or `2021-01-31`,
+/// with the `%Y %m %d %H %M %S` specifiers, in the offset Jira returned.
+pub fn format_date(timestamp: &str, format: &str) -> Option<String> {
+    let component = |range: std::ops::Range<usize>| -> Option<&str> {
+        let text = timestamp.get(range)?;
+        text.chars().all(|c| c.is_ascii_digit()).then_some(text)
+    };
+    let date = [component(0..4)?, component(5..7)?, component(8..10)?];
+    let time = match timestamp.get(10..11) {
+        Some("T") => [component(11..13)?, component(14..16)?, component(17..19)?],
+        _ => ["00", "00", "00"],
+    };
+
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => formatted.push_str(date[0]),
+            Some('m') => formatted.push_str(date[1]),
+            Some('d') => formatted.push_str(date[2]),
+            Some('H') => formatted.push_str(time[0]),
+            Some('M') => formatted.push_str(time[1]),
+            Some('S') => formatted.push_str(time[2]),
+            Some('%') => formatted.push('%'),
+            Some(other) => {
+                formatted.push('%');
+                formatted.push(other);
+            }
+            None => formatted.push('%'),
+        }
+    }
+    Some(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn issue() -> Value {
+        json!({
+            "key": "JC-42",
+            "fields": {
+                "summary": "Export reports as CSV",
+                "status": {"name": "In Progress"},
+                "assignee": {"displayName": "Ana Lima"},
+                "created": "2021-01-31T17:05:42.000+0000",
+                "duedate": "2021-02-15",
+                "labels": [],
+            }
+        })
+    }
+
+    fn render(template: &str) -> String {
+        let issue = issue();
+        Template::parse(template).unwrap().render(|path| {
+            path.iter()
+                .try_fold(&issue, |value, segment| value.get(segment))
+                .cloned()
+        })
+    }
+
+    fn parse_error(template: &str) -> String {
+        Template::parse(template).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn placeholders_and_nested_paths() {
+        assert_eq!(
+            render("{key}: {fields.summary} ({fields.assignee.displayName})"),
+            "JC-42: Export reports as CSV (Ana Lima)"
+        );
+        assert_eq!(render("{ key }"), "JC-42");
+    }
+
+    #[test]
+    fn missing_fields_render_empty() {
+        assert_eq!(render("[{fields.resolution}]"), "[]");
+        assert_eq!(render("[{fields.status.missing.deeper}]"), "[]");
+        assert_eq!(render("[{nothing}]"), "[]");
+    }
+
+    #[test]
+    fn case_filters() {
+        assert_eq!(render("{fields.status.name|upper}"), "IN PROGRESS");
+        assert_eq!(render("{fields.status.name|lower}"), "in progress");
+    }
+
+    #[test]
+    fn truncate_filter() {
+        assert_eq!(render("{fields.summary|truncate:6}"), "Export");
+        assert_eq!(render("{key|truncate:40}"), "JC-42");
+        assert_eq!(render("{key|truncate:0}"), "");
+    }
+
+    #[test]
+    fn date_filter() {
+        assert_eq!(render("{fields.created|date}"), "2021-01-31");
+        assert_eq!(
+            render("{fields.created|date:%d/%m/%Y %H:%M:%S}"),
+            "31/01/2021 17:05:42"
+        );
+        assert_eq!(render("{fields.duedate|date:%d.%m %H:%M}"), "15.02 00:00");
+        assert_eq!(render("{key|date}"), "JC-42");
+    }
+
+    #[test]
+    fn default_filter() {
+        assert_eq!(
+            render("{fields.resolution|default:Unresolved}"),
+            "Unresolved"
+        );
+        assert_eq!(render("{key|default:none}"), "JC-42");
+        assert_eq!(render("{fields.resolution|default:n/a|upper}"), "N/A");
+    }
+
+    #[test]
+    fn chained_filters_apply_in_order() {
+        assert_eq!(render("{fields.summary|upper|truncate:3}"), "EXP");
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(render("{key}\\t{{x}}\\n\\\\"), "JC-42\t{x}\n\\");
+        assert_eq!(render("a\\qb"), "a\\qb");
+    }
+
+    #[test]
+    fn malformed_templates() {
+        assert!(parse_error("{key").contains("unclosed `{`"));
+        assert!(parse_error("key}").contains("unmatched `}`"));
+        assert!(parse_error("{}").contains("empty placeholder"));
+        assert!(parse_error("{ |upper}").contains("empty placeholder"));
+        assert!(parse_error("{key|truncate:x}").contains("truncate expects a width"));
+        assert!(parse_error("{key|truncate}").contains("unknown filter `truncate`"));
+        assert!(parse_error("{key|bold}").contains("unknown filter `bold`"));
+    }
+
+    #[test]
+    fn columns_read_by_placeholders() {
+        let template =
+            Template::parse("{key} {fields.assignee.displayName} {Story Points} {key}").unwrap();
+        assert_eq!(template.columns(), vec!["key", "assignee", "Story Points"]);
+    }
+
+    #[test]
+    fn format_date_rejects_other_text() {
+        assert_eq!(format_date("yesterday", "%Y"), None);
+        assert_eq!(format_date("2021-1-5", "%Y"), None);
+        assert_eq!(
+            format_date("2021-01-31", "%Y%%%q"),
+            Some("2021%%q".to_string())
+        );
+    }
+}
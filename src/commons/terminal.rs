@@ -0,0 +1,125 @@
+use crate::commons::errors::{JiraError, JiraResult};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use terminal_size::{terminal_size, Height, Width};
+
+static DEFAULT_PAGER: &str = "less -FRX";
+static DEFAULT_EDITOR: &str = "vi";
+
+/// Whether stdout is an interactive terminal.
+pub fn is_tty() -> bool {
+    io::stdout().is_terminal()
+}
+
+/// Whether stdin is an interactive terminal rather than a pipe or file.
+pub fn is_stdin_tty() -> bool {
+    io::stdin().is_terminal()
+}
+
+/// Columns and rows of the terminal stdout is attached to, `COLUMNS` and
+/// `LINES` being used when it cannot be queried.
+pub fn size() -> Option<(usize, usize)> {
+    if !is_tty() {
+        return None;
+    }
+    window_size().or_else(|| {
+        let columns = env::var("COLUMNS").ok()?.parse().ok()?;
+        let lines = env::var("LINES")
+            .ok()
+            .and_then(|lines| lines.parse().ok())
+            .unwrap_or(usize::MAX);
+        Some((columns, lines))
+    })
+}
+
+fn window_size() -> Option<(usize, usize)> {
+    match terminal_size()? {
+        (Width(columns), Height(rows)) if columns > 0 => Some((columns as usize, rows as usize)),
+        _ => None,
+    }
+}
+
+/// Colors are used on a terminal unless `--no-color` or a non empty
+/// `NO_COLOR` environment variable turns them off.
+pub fn color_enabled(no_color: bool) -> bool {
+    let no_color_env = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    is_tty() && !no_color && !no_color_env
+}
+
+/// Writes `text` to stdout, through `$PAGER` (`less -FRX` by default) when it
+/// is taller than the terminal. An empty `$PAGER` disables paging.
+pub fn write_paged(text: &str, pager: bool) -> io::Result<()> {
+    let fits = match size() {
+        Some((_, lines)) => text.lines().count() < lines,
+        None => true,
+    };
+    let command = env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+
+    if !pager || fits || command.trim().is_empty() {
+        return write_stdout(text);
+    }
+
+    debug!("Paging output through {}", command);
+    let child = Command::new("sh")
+        .args(["-c", &command])
+        .stdin(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            debug!("Failed to start pager {}: {}", command, e);
+            return write_stdout(text);
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closes its input when quit before the end.
+        match stdin.write_all(text.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e),
+            _ => {}
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// Opens `text` in `$VISUAL` or `$EDITOR` (`vi` by default), from a temporary
+/// file ending with `file_name`, and returns the text saved.
+pub fn edit_text(file_name: &str, text: &str) -> JiraResult<String> {
+    let path = env::temp_dir().join(format!("jira-cli-{}-{}", std::process::id(), file_name));
+    let path_name = path.to_string_lossy().to_string();
+    fs::write(&path, text).map_err(|e| JiraError::io(&path_name, e))?;
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
+    debug!("Opening {} with {}", path_name, editor);
+    let status = Command::new("sh")
+        .args(["-c", &format!("{} \"$1\"", editor), "sh", &path_name])
+        .status()
+        .map_err(|e| JiraError::io(&editor, e));
+
+    let edited = status.and_then(|status| match status.success() {
+        true => fs::read_to_string(&path).map_err(|e| JiraError::io(&path_name, e)),
+        false => Err(JiraError::Config(format!(
+            "{} exited with {}, nothing was changed",
+            editor, status
+        ))),
+    });
+    let _ = fs::remove_file(&path);
+    edited
+}
+
+fn write_stdout(text: &str) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    match stdout
+        .write_all(text.as_bytes())
+        .and_then(|_| stdout.flush())
+    {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,20 @@
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub enum ConfigOps {
+    /// Interactively write a commented conf.yaml skeleton
+    #[structopt(name = "init")]
+    Init(ConfigInitOps),
+    /// Report every missing or invalid key of conf.yaml
+    #[structopt(name = "validate")]
+    Validate,
+    /// Print the resolved configuration with secrets redacted
+    #[structopt(name = "show")]
+    Show,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ConfigInitOps {
+    #[structopt(long = "force", help = "Overwrite an existing conf.yaml")]
+    pub force: bool,
+}
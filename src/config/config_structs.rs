@@ -0,0 +1,17 @@
+use crate::commons::config::HttpSettings;
+use serde::Serialize;
+
+pub struct ConfigHandler;
+
+/// Resolved configuration as printed by `config show`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigDisplay {
+    pub conf_path: String,
+    pub profile: String,
+    pub host: String,
+    pub auth: String,
+    pub user: Option<String>,
+    pub secret: Option<String>,
+    pub http: HttpSettings,
+    pub profiles: Vec<String>,
+}
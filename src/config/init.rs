@@ -0,0 +1,146 @@
+use crate::commons::config::{conf_path, AuthKind};
+use crate::config::command_args::ConfigInitOps;
+use crate::config::config_structs::ConfigHandler;
+use anyhow::bail;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use url::Url;
+
+impl ConfigHandler {
+    pub async fn init(&self, options: &ConfigInitOps) -> Result<(), anyhow::Error> {
+        let conf_path = conf_path()?;
+        if Path::new(&conf_path).exists() && !options.force {
+            bail!("{} already exists, use --force to overwrite it", &conf_path);
+        }
+
+        let name = prompt("Profile name", Some("default"))?;
+        let host = loop {
+            let host = prompt("Jira host, e.g. https://jira.example.com", None)?;
+            match Url::parse(&host) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => break host,
+                _ => eprintln!("{} is not an http(s) URL", host),
+            }
+        };
+        let auth = loop {
+            let auth = prompt(
+                "Auth method (basic, api_token, bearer, anonymous)",
+                Some("basic"),
+            )?;
+            match serde_yaml::from_str::<AuthKind>(&auth) {
+                Ok(auth) => break auth,
+                Err(_) => eprintln!("{} is not a known auth method", auth),
+            }
+        };
+        let user = match auth {
+            AuthKind::Basic | AuthKind::ApiToken => {
+                Some(prompt("User, or account email for Jira Cloud", None)?)
+            }
+            _ => None,
+        };
+        let password_command = match auth {
+            AuthKind::Anonymous => None,
+            _ => Some(prompt(
+                "Command printing the secret, e.g. `pass show jira` (empty to use JIRA_CLI_TOKEN)",
+                Some(""),
+            )?)
+            .filter(|command| !command.is_empty()),
+        };
+
+        let skeleton = skeleton(&name, &host, auth, user, password_command);
+        if let Some(dir) = Path::new(&conf_path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&conf_path, skeleton)?;
+        restrict_permissions(&conf_path)?;
+
+        println!("Configuration written to {}", &conf_path);
+        Ok(())
+    }
+}
+
+fn prompt(question: &str, default: Option<&str>) -> Result<String, anyhow::Error> {
+    loop {
+        match default {
+            Some(default) if !default.is_empty() => eprint!("{} [{}]: ", question, default),
+            _ => eprint!("{}: ", question),
+        }
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer)? == 0 {
+            bail!("no answer given for `{}`", question);
+        }
+        let answer = answer.trim();
+
+        match (answer.is_empty(), default) {
+            (false, _) => return Ok(answer.to_string()),
+            (true, Some(default)) => return Ok(default.to_string()),
+            (true, None) => eprintln!("a value is required"),
+        }
+    }
+}
+
+fn skeleton(
+    name: &str,
+    host: &str,
+    auth: AuthKind,
+    user: Option<String>,
+    password_command: Option<String>,
+) -> String {
+    let user = match user {
+        Some(user) => format!("    user: {}", yaml_scalar(&user)),
+        None => "    # user: jdoe".to_string(),
+    };
+    let password_command = match password_command {
+        Some(command) => format!("    password_command: {}", yaml_scalar(&command)),
+        None => "    # password_command: pass show jira".to_string(),
+    };
+
+    format!(
+        "# jira-cli configuration.
+# Check it with `jira-cli config validate` and `jira-cli config show`.
+
+# Profile used when neither --profile nor JIRA_CLI_PROFILE is given.
+default_profile: {name}
+
+profiles:
+  {name}:
+    # Base URL of the Jira instance.
+    host: {host}
+    # One of basic, api_token, bearer or anonymous.
+    auth: {auth}
+    # Username, or account email for Jira Cloud API tokens.
+{user}
+    # Command printing the password or token on the first line of its stdout.
+    # JIRA_CLI_TOKEN takes precedence over it.
+{password_command}
+    # Plain text secrets work too but are discouraged.
+    # password: secret
+    # token: secret
+",
+        name = yaml_scalar(name),
+        host = yaml_scalar(host),
+        auth = auth,
+        user = user,
+        password_command = password_command,
+    )
+}
+
+fn yaml_scalar(value: &str) -> String {
+    serde_yaml::to_string(value)
+        .map(|yaml| yaml.trim_start_matches("---").trim().to_string())
+        .unwrap_or_else(|_| format!("{:?}", value))
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &str) -> Result<(), anyhow::Error> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &str) -> Result<(), anyhow::Error> {
+    Ok(())
+}
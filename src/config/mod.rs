@@ -0,0 +1,5 @@
+pub mod command_args;
+pub mod config_structs;
+mod init;
+mod show;
+mod validate;
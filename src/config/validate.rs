@@ -0,0 +1,299 @@
+use crate::commons::config::{conf_path, AuthKind, HttpSettings, Profile, LEGACY_PROFILE};
+use crate::commons::file_utilities::load_yaml;
+use crate::config::config_structs::ConfigHandler;
+use anyhow::{anyhow, bail};
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use url::Url;
+
+static TOP_LEVEL_KEYS: [&str; 4] = ["default_profile", "profiles", "jira", "columns"];
+
+impl ConfigHandler {
+    pub async fn validate(&self) -> Result<(), anyhow::Error> {
+        let conf_path = conf_path()?;
+        if !Path::new(&conf_path).exists() {
+            bail!("{} not found, run `jira-cli config init`", &conf_path);
+        }
+
+        let conf_string = load_yaml(&conf_path).await?;
+        let conf: Value = serde_yaml::from_str(&conf_string)
+            .map_err(|e| anyhow!("{} is not valid YAML: {}", &conf_path, e))?;
+
+        let problems = match conf.as_mapping() {
+            Some(conf) => config_problems(conf),
+            None => vec!["the top level of the file must be a mapping".to_string()],
+        };
+
+        if problems.is_empty() {
+            println!("{} is valid", &conf_path);
+            return Ok(());
+        }
+
+        for problem in &problems {
+            println!("- {}", problem);
+        }
+        bail!("{} has {} problem(s)", &conf_path, problems.len())
+    }
+}
+
+fn config_problems(conf: &Mapping) -> Vec<String> {
+    let mut problems = vec![];
+    let mut names = vec![];
+
+    for (key, _) in conf.iter() {
+        match key.as_str() {
+            Some(key) if TOP_LEVEL_KEYS.contains(&key) => {}
+            _ => problems.push(format!(
+                "unknown key `{}`, expected one of {}",
+                key_name(key),
+                TOP_LEVEL_KEYS.join(", ")
+            )),
+        }
+    }
+
+    match conf.get(&Value::from("profiles")) {
+        None => {}
+        Some(Value::Mapping(profiles)) => {
+            for (name, profile) in profiles {
+                let name = key_name(name);
+                problems.extend(profile_problems(&format!("profiles.{}", name), profile));
+                names.push(name);
+            }
+        }
+        Some(_) => problems.push("`profiles` must be a mapping of profile names".to_string()),
+    }
+
+    if let Some(profile) = conf.get(&Value::from("jira")) {
+        problems.extend(profile_problems("jira", profile));
+        names.push(LEGACY_PROFILE.to_string());
+    }
+
+    match conf.get(&Value::from("default_profile")) {
+        None => {
+            if names.len() > 1 {
+                problems.push(
+                    "`default_profile` is missing, it is required with several profiles"
+                        .to_string(),
+                );
+            }
+        }
+        Some(Value::String(default)) => {
+            if !names.contains(default) {
+                problems.push(format!(
+                    "`default_profile` is `{}` but no such profile is declared",
+                    default
+                ));
+            }
+        }
+        Some(_) => problems.push("`default_profile` must be a profile name".to_string()),
+    }
+
+    if let Some(columns) = conf.get(&Value::from("columns")) {
+        if serde_yaml::from_value::<BTreeMap<String, Vec<String>>>(columns.clone()).is_err() {
+            problems.push("`columns` must map command names to lists of columns".to_string());
+        }
+    }
+
+    if names.is_empty() {
+        problems.push("no profiles declared under `profiles`".to_string());
+    }
+
+    problems
+}
+
+/// Problems of each key of a profile, then of the profile as a whole, the
+/// invalid keys being left out of the latter checks.
+fn profile_problems(path: &str, profile: &Value) -> Vec<String> {
+    let mut problems = vec![];
+    let mut keys = valid_keys::<Profile>(path, profile, &mut problems);
+    if let Some(http) = profile
+        .as_mapping()
+        .and_then(|profile| profile.get(&Value::from("http")))
+        .filter(|http| http.is_mapping())
+    {
+        let http_path = format!("{}.http", path);
+        let http = valid_keys::<HttpSettings>(&http_path, http, &mut problems);
+        keys.insert(Value::from("http"), Value::Mapping(http));
+    }
+    let profile: Profile = serde_yaml::from_value(Value::Mapping(keys)).unwrap_or_default();
+
+    match &profile.host {
+        None => problems.push(format!("`{}.host` is missing", path)),
+        Some(host) => match Url::parse(host) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            _ => problems.push(format!(
+                "`{}.host` must be an http(s) URL, got `{}`",
+                path, host
+            )),
+        },
+    }
+
+    let has_secret =
+        profile.password_command.is_some() || profile.password.is_some() || profile.token.is_some();
+    if let Some(auth) = profile.auth {
+        let needs_user = auth == AuthKind::Basic || auth == AuthKind::ApiToken;
+        if needs_user && profile.user.is_none() {
+            problems.push(format!("`{}.user` is required by {} auth", path, auth));
+        }
+        if auth != AuthKind::Anonymous && !has_secret {
+            problems.push(format!(
+                "`{}` uses {} auth but has no password_command, password or token, JIRA_CLI_TOKEN must be set",
+                path, auth
+            ));
+        }
+    }
+    if let Some(proxy) = &profile.http.proxy {
+        if Url::parse(proxy).is_err() {
+            problems.push(format!(
+                "`{}.http.proxy` is not a valid URL: `{}`",
+                path, proxy
+            ));
+        }
+    }
+    for ca_cert in &profile.http.ca_certs {
+        if !Path::new(ca_cert).is_file() {
+            problems.push(format!(
+                "`{}.http.ca_certs` lists `{}` which is not a file",
+                path, ca_cert
+            ));
+        }
+    }
+    if profile.http.timeout == Some(0) || profile.http.connect_timeout == Some(0) {
+        problems.push(format!(
+            "`{}.http` timeouts must be greater than zero",
+            path
+        ));
+    }
+    if let Some(command) = &profile.password_command {
+        if command.trim().is_empty() {
+            problems.push(format!("`{}.password_command` is empty", path));
+        }
+    }
+
+    problems
+}
+
+/// The keys of the mapping `value` that are valid in a `T`, each other key
+/// being reported under `path`. Keys holding a mapping are only checked to be
+/// one.
+fn valid_keys<T: DeserializeOwned>(
+    path: &str,
+    value: &Value,
+    problems: &mut Vec<String>,
+) -> Mapping {
+    let mapping = match value {
+        Value::Mapping(mapping) => mapping,
+        Value::Null => return Mapping::new(),
+        _ => {
+            problems.push(format!("`{}` must be a mapping", path));
+            return Mapping::new();
+        }
+    };
+    let mut valid = Mapping::new();
+    for (key, value) in mapping {
+        let checked = match value {
+            Value::Mapping(_) => Value::Mapping(Mapping::new()),
+            value => value.clone(),
+        };
+        let mut single = Mapping::new();
+        single.insert(key.clone(), checked);
+        match serde_yaml::from_value::<T>(Value::Mapping(single)) {
+            Ok(_) => {
+                valid.insert(key.clone(), value.clone());
+            }
+            Err(e) => problems.push(format!("`{}.{}`: {}", path, key_name(key), e)),
+        }
+    }
+    valid
+}
+
+fn key_name(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        other => serde_yaml::to_string(other)
+            .map(|key| key.trim_start_matches("---").trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(yaml: &str) -> Vec<String> {
+        let conf: Value = serde_yaml::from_str(yaml).unwrap();
+        config_problems(conf.as_mapping().unwrap())
+    }
+
+    #[test]
+    fn valid_configuration() {
+        let yaml = "default_profile: main\n\
+                    profiles:\n  \
+                      main:\n    host: https://jira.example.com\n    auth: bearer\n    token: pat\n    \
+                        http:\n      timeout: 60\n      retries: 2\n  \
+                      other:\n    host: http://localhost:8080\n\
+                    columns:\n  search: [key, summary]\n";
+        assert!(problems(yaml).is_empty(), "{:?}", problems(yaml));
+    }
+
+    #[test]
+    fn every_invalid_key_of_a_profile_is_reported() {
+        let yaml = "profiles:\n  \
+                      main:\n    host: ftp://jira.example.com\n    auth: oauth\n    \
+                        passwrd: secret\n    user: [jdoe]\n    \
+                        http:\n      timeout: soon\n      proxi: http://proxy\n      retries: 2\n";
+        let problems = problems(yaml);
+        let keys = [
+            "`profiles.main.auth`: unknown variant `oauth`",
+            "`profiles.main.passwrd`: unknown field `passwrd`",
+            "`profiles.main.user`: invalid type",
+            "`profiles.main.http.timeout`: invalid type",
+            "`profiles.main.http.proxi`: unknown field `proxi`",
+            "`profiles.main.host` must be an http(s) URL, got `ftp://jira.example.com`",
+        ];
+        for key in keys.iter() {
+            assert!(
+                problems.iter().any(|problem| problem.starts_with(key)),
+                "{} not in {:?}",
+                key,
+                problems
+            );
+        }
+        assert_eq!(problems.len(), keys.len(), "{:?}", problems);
+    }
+
+    #[test]
+    fn checks_use_the_valid_keys() {
+        let yaml = "jira:\n  auth: basic\n  token: 42\n  http: 5\n";
+        assert_eq!(
+            problems(yaml),
+            vec![
+                "`jira.token`: invalid type: integer `42`, expected a string",
+                "`jira.http`: invalid type: integer `5`, expected struct HttpSettings",
+                "`jira.host` is missing",
+                "`jira.user` is required by basic auth",
+                "`jira` uses basic auth but has no password_command, password or token, \
+                 JIRA_CLI_TOKEN must be set",
+            ]
+        );
+    }
+
+    #[test]
+    fn top_level_problems() {
+        let yaml = "profile: main\n\
+                    profiles:\n  a:\n    host: https://a.example.com\n  b: 3\n\
+                    columns: [key]\n";
+        assert_eq!(
+            problems(yaml),
+            vec![
+                "unknown key `profile`, expected one of default_profile, profiles, jira, columns",
+                "`profiles.b` must be a mapping",
+                "`profiles.b.host` is missing",
+                "`default_profile` is missing, it is required with several profiles",
+                "`columns` must map command names to lists of columns",
+            ]
+        );
+    }
+}
@@ -1,7 +1,12 @@
+use crate::commons::command_args::ListingOps;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
 pub struct EpicOps {
     #[structopt(long = "project", short = "p")]
     pub(crate) project_key: String,
+    #[structopt(long = "limit", help = "Maximum number of epics to list.")]
+    pub(crate) limit: Option<usize>,
+    #[structopt(flatten)]
+    pub(crate) listing: ListingOps,
 }
@@ -1,63 +1,40 @@
-use crate::commons::req_builder::build_get_req;
-use crate::commons::structs::{AuthOptions, Issue, JQL, REST_URI};
-use crate::epics::epics_projects::{Epic, EpicHandler};
+use crate::commons::client::JiraClient;
+use crate::commons::custom_fields::CustomFieldsHandler;
+use crate::commons::errors::JiraResult;
+use crate::commons::output::{Listing, RenderOptions, LIST_COLUMNS};
+use crate::commons::search::IssueSearch;
+use crate::epics::epics_projects::EpicHandler;
 use crate::EpicOps;
-use term_table::{
-    row::Row,
-    table_cell::{Alignment, TableCell},
-    Table, TableStyle,
-};
-use url::Url;
 
 // Query para listar epicos no jira.
 // https://jira.bradesco.com.br:8443/rest/api/2/search?jql=PROJECT=ESTRT AND issuetype="Epic"&fields=summary
 
 impl EpicHandler {
-    pub async fn list(&self, options: &EpicOps, auth_options: &AuthOptions) {
-        let uri = format!("{}{}", &auth_options.host, &REST_URI);
-        let project = options.project_key.clone();
-        let jql_query = format!(
-            "{}{}{}{}{}",
-            &uri, &JQL, "PROJECT=", project, " AND issuetype=Epic&fields=summary,description"
-        );
-        let url = Url::parse(&jql_query).unwrap();
-
-        let epics = build_get_req(url, auth_options)
-            .send()
-            .await
-            .unwrap()
-            .json::<Epic>()
-            .await
-            .unwrap();
-
-        let mut table = Table::new();
-        table.max_column_width = 80;
-        table.style = TableStyle::blank();
-        table.add_row(build_table_header_row());
-
-        for issue in epics.issues.unwrap() {
-            table.add_row(build_table_body(issue));
-        }
-
-        info!("{}", table.render());
+    pub async fn list(
+        &self,
+        options: &EpicOps,
+        client: &JiraClient,
+        render: &RenderOptions,
+    ) -> JiraResult<()> {
+        let shown = options
+            .listing
+            .shown_columns(render, "epics", &LIST_COLUMNS);
+        let columns = options.listing.column_names(&shown);
+        let columns = CustomFieldsHandler
+            .resolve_columns(client, Some(&options.project_key), &columns)
+            .await?;
+
+        let jql = format!("project=\"{}\" AND issuetype=Epic", &options.project_key);
+
+        let epics = IssueSearch::new(client, &jql)
+            .columns(&columns)
+            .limit(options.limit)
+            .collect()
+            .await?;
+
+        Listing::from_issues(&epics.issues, &columns)
+            .footer(epics.footer())
+            .arrange(&options.listing, &shown)?
+            .print(render)
     }
 }
-
-fn build_table_body(issue: Issue) -> Row<'static> {
-    let fields = issue.fields.unwrap();
-    Row::new(vec![
-        TableCell::new_with_alignment(fields.summary.unwrap(), 1, Alignment::Left),
-        TableCell::new_with_alignment(issue.key, 2, Alignment::Left),
-        TableCell::new_with_alignment(issue.id, 1, Alignment::Left),
-        TableCell::new_with_alignment(issue.issue_link, 1, Alignment::Left),
-    ])
-}
-
-fn build_table_header_row() -> Row<'static> {
-    Row::new(vec![
-        TableCell::new_with_alignment("Name", 1, Alignment::Left),
-        TableCell::new_with_alignment("Key", 2, Alignment::Left),
-        TableCell::new_with_alignment("ID", 1, Alignment::Left),
-        TableCell::new_with_alignment("Link", 1, Alignment::Left),
-    ])
-}
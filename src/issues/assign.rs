@@ -0,0 +1,223 @@
+use crate::commons::client::JiraClient;
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::req_builder::{build_get_req, build_put_req};
+use crate::commons::structs::{is_account_id, REST_URI};
+use crate::issues::command_args::{IssueAssignOps, IssueViewOps};
+use crate::issues::issues_structs::{issue_url, IssueHandler, ServerInfo, User};
+use serde_json::{json, Value};
+use url::Url;
+
+static ME: &str = "me";
+static CLOUD: &str = "Cloud";
+static MYSELF_URI: &str = "/myself";
+static SERVER_INFO_URI: &str = "/serverInfo";
+static ASSIGNABLE_URI: &str = "/user/assignable/search";
+static ASSIGNEE_SEGMENT: &str = "assignee";
+static MAX_USERS: usize = 50;
+
+impl IssueHandler {
+    pub async fn assign(&self, options: &IssueAssignOps, client: &JiraClient) -> JiraResult<()> {
+        let (reference, name) = assignee(client, &options.key, &options.user).await?;
+        set_assignee(client, &options.key, reference).await?;
+        println!("Assigned {} to {}", &options.key, name);
+        Ok(())
+    }
+
+    pub async fn unassign(&self, options: &IssueViewOps, client: &JiraClient) -> JiraResult<()> {
+        let reference = match is_cloud(client).await? {
+            true => json!({ "accountId": null }),
+            false => json!({ "name": null }),
+        };
+        set_assignee(client, &options.key, reference).await?;
+        println!("Unassigned {}", &options.key);
+        Ok(())
+    }
+}
+
+/// Reference to the user matching `query` among the users assignable to
+/// `key`, `{"accountId": ...}` on Jira Cloud and `{"name": ...}` on Server,
+/// along with the display name of the user.
+///
+/// `query` is `me`, an account id on Cloud, or else searched for and picked
+/// by `pick_user`.
+pub(super) async fn assignee(
+    client: &JiraClient,
+    key: &str,
+    query: &str,
+) -> JiraResult<(Value, String)> {
+    let cloud = is_cloud(client).await?;
+    let user = match query.trim() {
+        query if query.eq_ignore_ascii_case(ME) => {
+            let url = Url::parse(&format!("{}{}{}", client.host(), REST_URI, MYSELF_URI))?;
+            client.send_json::<User>(build_get_req(url, client)).await?
+        }
+        query if cloud && is_account_id(query) => {
+            return Ok((json!({ "accountId": query }), query.to_string()))
+        }
+        query => find_user(client, cloud, key, query).await?,
+    };
+
+    let name = user
+        .display_name
+        .clone()
+        .or_else(|| user.name.clone())
+        .unwrap_or_else(|| query.to_string());
+    let reference = match (cloud, user.account_id, user.name) {
+        (true, Some(account_id), _) => json!({ "accountId": account_id }),
+        (false, _, Some(user_name)) => json!({ "name": user_name }),
+        _ => {
+            let missing = if cloud { "account id" } else { "name" };
+            return Err(JiraError::deserialize(
+                "the user",
+                format!("{} has no {}", name, missing),
+            ));
+        }
+    };
+    Ok((reference, name))
+}
+
+async fn set_assignee(client: &JiraClient, key: &str, reference: Value) -> JiraResult<()> {
+    let url = issue_url(client, key, &[ASSIGNEE_SEGMENT])?;
+    debug!("Assigning {} with {}", url, reference);
+    client
+        .send(build_put_req(url, client).json(&reference))
+        .await?;
+    Ok(())
+}
+
+/// Whether the host is Jira Cloud, whose user references are account ids.
+async fn is_cloud(client: &JiraClient) -> JiraResult<bool> {
+    if let Some(cloud) = client.cloud.get() {
+        return Ok(*cloud);
+    }
+    let url = Url::parse(&format!("{}{}{}", client.host(), REST_URI, SERVER_INFO_URI))?;
+    let info = client
+        .send_json::<ServerInfo>(build_get_req(url, client))
+        .await?;
+    let cloud = info.deployment_type.as_deref() == Some(CLOUD);
+    Ok(*client.cloud.get_or_init(|| cloud))
+}
+
+/// Searches the users assignable to `key`, by `query` on Cloud and by
+/// `username` on Server, which both match names, emails and display names.
+async fn find_user(client: &JiraClient, cloud: bool, key: &str, query: &str) -> JiraResult<User> {
+    let mut url = Url::parse(&format!("{}{}{}", client.host(), REST_URI, ASSIGNABLE_URI))?;
+    url.query_pairs_mut()
+        .append_pair("issueKey", key)
+        .append_pair(if cloud { "query" } else { "username" }, query)
+        .append_pair("maxResults", &MAX_USERS.to_string());
+    debug!("Searching users {}", url);
+
+    let users = client
+        .send_json::<Vec<User>>(build_get_req(url, client))
+        .await?;
+    pick_user(users, key, query)
+}
+
+/// The user whose name, account id, email or display name is `query`, else
+/// whose display name has `query` as whole words, else contains it. Users the
+/// search returned without matching any of these are not picked.
+fn pick_user(users: Vec<User>, key: &str, query: &str) -> JiraResult<User> {
+    let wanted = normalize(query);
+    let rank = |user: &User| {
+        let exact = [
+            &user.name,
+            &user.account_id,
+            &user.email_address,
+            &user.display_name,
+        ]
+        .iter()
+        .any(|value| value.as_deref().map(normalize).as_deref() == Some(wanted.as_str()));
+        let display_name = normalize(user.display_name.as_deref().unwrap_or_default());
+        match () {
+            _ if exact => Some(0),
+            _ if format!(" {} ", display_name).contains(&format!(" {} ", wanted)) => Some(1),
+            _ if display_name.contains(&wanted) => Some(2),
+            _ => None,
+        }
+    };
+
+    let best = users.iter().filter_map(rank).min();
+    let matches = users
+        .iter()
+        .filter(|user| best.is_some() && rank(user) == best)
+        .collect::<Vec<&User>>();
+
+    match matches.as_slice() {
+        [user] => Ok((*user).clone()),
+        [] => Err(JiraError::NotFound(format!(
+            "user `{}` assignable to {}",
+            query, key
+        ))),
+        _ => Err(JiraError::Config(format!(
+            "`{}` matches several users: {}",
+            query,
+            matches
+                .iter()
+                .map(|user| describe(user))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// `Display Name (name)`, the account id standing for the name on Cloud.
+fn describe(user: &User) -> String {
+    let id = user
+        .name
+        .as_deref()
+        .or(user.account_id.as_deref())
+        .unwrap_or_default();
+    match &user.display_name {
+        Some(display_name) => format!("{} ({})", display_name, id),
+        None => id.to_string(),
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str, display_name: &str) -> User {
+        User {
+            name: Some(name.to_string()),
+            account_id: None,
+            display_name: Some(display_name.to_string()),
+            email_address: Some(format!("{}@example.com", name)),
+        }
+    }
+
+    #[test]
+    fn exact_match_wins_over_partial_ones() {
+        let users = vec![user("ann", "Ann Lee"), user("joann", "Joann Ward")];
+        assert_eq!(
+            pick_user(users, "JC-1", "ann").unwrap().name.unwrap(),
+            "ann"
+        );
+    }
+
+    #[test]
+    fn several_matches_of_the_same_rank_are_ambiguous() {
+        let users = vec![user("alee", "Ann Lee"), user("blee", "Bob Lee")];
+        assert!(matches!(
+            pick_user(users, "JC-1", "lee"),
+            Err(JiraError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn users_matching_nothing_are_not_picked() {
+        let users = vec![user("jdoe", "John Doe")];
+        assert!(matches!(
+            pick_user(users, "JC-1", "smith"),
+            Err(JiraError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+use structopt::StructOpt;
+
+#[allow(clippy::large_enum_variant)]
+#[derive(StructOpt, Debug)]
+pub enum IssueOps {
+    /// Show every detail of an issue
+    #[structopt(name = "view")]
+    View(IssueViewOps),
+    /// Edit an issue from flags, or in $EDITOR when none is given
+    #[structopt(name = "edit")]
+    Edit(IssueEditOps),
+    /// List the workflow transitions available to an issue
+    #[structopt(name = "transitions")]
+    Transitions(IssueViewOps),
+    /// Move issues through their workflow
+    #[structopt(name = "move")]
+    Move(IssueMoveOps),
+    /// Assign an issue to a user
+    #[structopt(name = "assign")]
+    Assign(IssueAssignOps),
+    /// Remove the assignee of an issue
+    #[structopt(name = "unassign")]
+    Unassign(IssueViewOps),
+    /// Add, list, edit or delete the comments of an issue
+    #[structopt(name = "comment")]
+    Comment(CommentOps),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct IssueViewOps {
+    #[structopt(help = "Issue key, e.g. JC-42.")]
+    pub key: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct IssueEditOps {
+    #[structopt(help = "Issue key, e.g. JC-42.")]
+    pub key: String,
+    #[structopt(long = "summary", short = "s")]
+    pub summary: Option<String>,
+    #[structopt(long = "description", short = "d")]
+    pub description: Option<String>,
+    #[structopt(
+        long = "labels",
+        help = "Comma separated labels replacing the current ones.",
+        use_delimiter = true,
+        conflicts_with_all = &["add-labels", "remove-labels"]
+    )]
+    pub labels: Option<Vec<String>>,
+    #[structopt(long = "add-label", number_of_values = 1)]
+    pub add_labels: Vec<String>,
+    #[structopt(long = "remove-label", number_of_values = 1)]
+    pub remove_labels: Vec<String>,
+    #[structopt(
+        long = "components",
+        help = "Comma separated components replacing the current ones.",
+        use_delimiter = true,
+        conflicts_with_all = &["add-components", "remove-components"]
+    )]
+    pub components: Option<Vec<String>>,
+    #[structopt(long = "add-component", number_of_values = 1)]
+    pub add_components: Vec<String>,
+    #[structopt(long = "remove-component", number_of_values = 1)]
+    pub remove_components: Vec<String>,
+    #[structopt(long = "priority", help = "Priority name, e.g. High.")]
+    pub priority: Option<String>,
+    #[structopt(
+        long = "assignee",
+        help = "`me`, an account id, a user name or part of a display name."
+    )]
+    pub assignee: Option<String>,
+    #[structopt(long = "due-date", help = "Due date as YYYY-MM-DD.")]
+    pub due_date: Option<String>,
+    #[structopt(
+        long = "field",
+        short = "F",
+        help = "Field to set as \"Name=value\", the name being a field id or custom field name.",
+        number_of_values = 1
+    )]
+    pub fields: Vec<String>,
+}
+
+impl IssueEditOps {
+    /// Whether any field flag was given, the editor being opened otherwise.
+    pub fn has_changes(&self) -> bool {
+        self.summary.is_some()
+            || self.description.is_some()
+            || self.labels.is_some()
+            || !self.add_labels.is_empty()
+            || !self.remove_labels.is_empty()
+            || self.components.is_some()
+            || !self.add_components.is_empty()
+            || !self.remove_components.is_empty()
+            || self.priority.is_some()
+            || self.assignee.is_some()
+            || self.due_date.is_some()
+            || !self.fields.is_empty()
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct IssueAssignOps {
+    #[structopt(help = "Issue key, e.g. JC-42.")]
+    pub key: String,
+    #[structopt(
+        help = "`me`, an account id, a user name or part of a display name, among the users assignable to the issue."
+    )]
+    pub user: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct IssueMoveOps {
+    #[structopt(
+        name = "KEY... TRANSITION",
+        help = "Issue keys followed by the transition or target status, matched case-insensitively by its beginning or part of it. Keys are read from stdin when none or `-` is given and --jql is not used.",
+        required = true
+    )]
+    pub args: Vec<String>,
+    #[structopt(long = "jql", help = "Moves the issues found by this query.")]
+    pub jql: Option<String>,
+    #[structopt(long = "resolution", help = "Resolution name, e.g. Done.")]
+    pub resolution: Option<String>,
+    #[structopt(
+        long = "field",
+        short = "F",
+        help = "Field of the transition screen as \"Name=value\", the name being a field id or custom field name.",
+        number_of_values = 1
+    )]
+    pub fields: Vec<String>,
+}
+
+impl IssueMoveOps {
+    pub fn keys(&self) -> &[String] {
+        &self.args[..self.args.len() - 1]
+    }
+
+    pub fn transition(&self) -> &str {
+        &self.args[self.args.len() - 1]
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub enum CommentOps {
+    /// Comment on an issue
+    #[structopt(name = "add")]
+    Add(CommentAddOps),
+    /// List the comments of an issue
+    #[structopt(name = "list")]
+    List(IssueViewOps),
+    /// Change the text or visibility of a comment
+    #[structopt(name = "edit")]
+    Edit(CommentEditOps),
+    /// Delete a comment
+    #[structopt(name = "delete")]
+    Delete(CommentDeleteOps),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CommentAddOps {
+    #[structopt(help = "Issue key, e.g. JC-42.")]
+    pub key: String,
+    #[structopt(
+        help = "Comment text in Jira markup, read from stdin when `-` or piped and written in $EDITOR otherwise."
+    )]
+    pub body: Option<String>,
+    #[structopt(
+        long = "visibility",
+        help = "Restricts the comment to a project role or group, as \"role:Developers\" or \"group:jira-users\", a bare name being a role."
+    )]
+    pub visibility: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CommentEditOps {
+    #[structopt(help = "Issue key, e.g. JC-42.")]
+    pub key: String,
+    #[structopt(help = "Comment id, as shown by `issue comment list`.")]
+    pub id: String,
+    #[structopt(
+        help = "New comment text, read from stdin when `-` or piped and edited in $EDITOR otherwise."
+    )]
+    pub body: Option<String>,
+    #[structopt(
+        long = "visibility",
+        help = "Restricts the comment to a project role or group, as \"role:Developers\" or \"group:jira-users\", a bare name being a role."
+    )]
+    pub visibility: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CommentDeleteOps {
+    #[structopt(help = "Issue key, e.g. JC-42.")]
+    pub key: String,
+    #[structopt(help = "Comment id, as shown by `issue comment list`.")]
+    pub id: String,
+}
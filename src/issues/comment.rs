@@ -0,0 +1,228 @@
+use crate::commons::client::JiraClient;
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::output::{serialize, Listing, OutputFormat, RenderOptions};
+use crate::commons::req_builder::{build_delete_req, build_get_req, build_post_req, build_put_req};
+use crate::commons::structs::{DATE_TIME_FORMAT, STDIN};
+use crate::commons::table::{field_text, styled, Cell, TableLayout};
+use crate::commons::template::format_date;
+use crate::commons::terminal::{edit_text, is_stdin_tty, write_paged};
+use crate::issues::command_args::{CommentAddOps, CommentDeleteOps, CommentEditOps, CommentOps};
+use crate::issues::issues_structs::{
+    issue_url, Comment, CommentPage, CommentRequest, IssueHandler, Visibility,
+};
+use crate::issues::markup::render_markup;
+use serde_json::{json, Value};
+use std::io::Read;
+
+static COMMENT_SEGMENT: &str = "comment";
+static PAGE_SIZE: usize = 100;
+
+impl IssueHandler {
+    pub async fn comment(
+        &self,
+        options: CommentOps,
+        client: &JiraClient,
+        render: &RenderOptions,
+    ) -> JiraResult<()> {
+        match options {
+            CommentOps::Add(args) => add_comment(&args, client).await,
+            CommentOps::List(args) => list_comments(&args.key, client, render).await,
+            CommentOps::Edit(args) => edit_comment(&args, client).await,
+            CommentOps::Delete(args) => delete_comment(&args, client).await,
+        }
+    }
+}
+
+async fn add_comment(options: &CommentAddOps, client: &JiraClient) -> JiraResult<()> {
+    let request = CommentRequest {
+        body: read_body(options.body.as_deref(), "")?,
+        visibility: options.visibility.as_deref().map(visibility).transpose()?,
+    };
+
+    let url = issue_url(client, &options.key, &[COMMENT_SEGMENT])?;
+    let comment = client
+        .send_json::<Comment>(build_post_req(url, client).json(&request))
+        .await?;
+    println!("Added comment {} to {}", comment.id, &options.key);
+    Ok(())
+}
+
+async fn list_comments(key: &str, client: &JiraClient, render: &RenderOptions) -> JiraResult<()> {
+    let comments = fetch_comments(client, key).await?;
+
+    let text = match (&render.template, render.format) {
+        (None, OutputFormat::Table) => render_comments(key, &comments, render.layout),
+        (None, OutputFormat::Json) | (None, OutputFormat::Yaml) => {
+            serialize(&comments, render.format)?
+        }
+        _ => {
+            let mut listing =
+                Listing::new(&["id", "author", "created", "updated", "visibility", "body"]);
+            for comment in &comments {
+                listing.rows.push(vec![
+                    Value::from(comment.id.clone()),
+                    comment.author.clone(),
+                    json!(comment.created),
+                    json!(comment.updated),
+                    json!(comment.visibility.as_ref().map(Visibility::to_string)),
+                    Value::from(comment.body.clone()),
+                ]);
+                listing
+                    .sources
+                    .push(serde_json::to_value(comment).unwrap_or_default());
+            }
+            return listing.print(render);
+        }
+    };
+    write_paged(&text, render.pager).map_err(|e| JiraError::io("stdout", e))
+}
+
+/// Replaces the text of a comment, keeping its visibility unless
+/// `--visibility` is given. The current text is edited when no new one is
+/// given and stdin is a terminal.
+async fn edit_comment(options: &CommentEditOps, client: &JiraClient) -> JiraResult<()> {
+    let comment = fetch_comment(client, &options.key, &options.id).await?;
+    let body = match (&options.body, &options.visibility) {
+        (None, Some(_)) if is_stdin_tty() => comment.body.clone(),
+        (body, _) => read_body(body.as_deref(), &comment.body)?,
+    };
+    let visibility = match &options.visibility {
+        Some(text) => Some(visibility(text)?),
+        None => comment.visibility.clone(),
+    };
+    if body == comment.body && options.visibility.is_none() {
+        println!("No changes to comment {} of {}", &options.id, &options.key);
+        return Ok(());
+    }
+
+    let request = CommentRequest { body, visibility };
+    let url = issue_url(client, &options.key, &[COMMENT_SEGMENT, &options.id])?;
+    client
+        .send(build_put_req(url, client).json(&request))
+        .await?;
+    println!("Updated comment {} of {}", &options.id, &options.key);
+    Ok(())
+}
+
+async fn delete_comment(options: &CommentDeleteOps, client: &JiraClient) -> JiraResult<()> {
+    let url = issue_url(client, &options.key, &[COMMENT_SEGMENT, &options.id])?;
+    client
+        .send(build_delete_req(url, client))
+        .await
+        .map_err(|e| comment_not_found(e, &options.key, &options.id))?;
+    println!("Deleted comment {} of {}", &options.id, &options.key);
+    Ok(())
+}
+
+/// Every comment of an issue, oldest first.
+async fn fetch_comments(client: &JiraClient, key: &str) -> JiraResult<Vec<Comment>> {
+    let mut comments = vec![];
+    loop {
+        let mut url = issue_url(client, key, &[COMMENT_SEGMENT])?;
+        url.query_pairs_mut()
+            .append_pair("startAt", &comments.len().to_string())
+            .append_pair("maxResults", &PAGE_SIZE.to_string());
+        debug!("Fetching comments {}", url);
+
+        let page = client
+            .send_json::<CommentPage>(build_get_req(url, client))
+            .await?;
+        let fetched = page.comments.len();
+        comments.extend(page.comments);
+        if fetched == 0 || comments.len() >= page.total.unwrap_or(0) {
+            return Ok(comments);
+        }
+    }
+}
+
+async fn fetch_comment(client: &JiraClient, key: &str, id: &str) -> JiraResult<Comment> {
+    let url = issue_url(client, key, &[COMMENT_SEGMENT, id])?;
+    client
+        .send_json::<Comment>(build_get_req(url, client))
+        .await
+        .map_err(|e| comment_not_found(e, key, id))
+}
+
+fn comment_not_found(error: JiraError, key: &str, id: &str) -> JiraError {
+    match error {
+        JiraError::NotFound(_) => JiraError::NotFound(format!("comment {} of {}", id, key)),
+        e => e,
+    }
+}
+
+/// The comment text: the argument, stdin when `-` or piped, else written in
+/// the editor starting from `current`.
+fn read_body(body: Option<&str>, current: &str) -> JiraResult<String> {
+    let body = match body {
+        Some(body) if body != STDIN => body.to_string(),
+        None if is_stdin_tty() => edit_text("comment.txt", current)?,
+        _ => {
+            let mut body = String::new();
+            std::io::stdin()
+                .read_to_string(&mut body)
+                .map_err(|e| JiraError::io("stdin", e))?;
+            body
+        }
+    };
+
+    let body = body.trim_end();
+    if body.trim().is_empty() {
+        return Err(JiraError::Config("the comment is empty".to_string()));
+    }
+    Ok(body.to_string())
+}
+
+/// `role:NAME`, `group:NAME` or a bare role name.
+fn visibility(text: &str) -> JiraResult<Visibility> {
+    let (kind, value) = match text.split_once(':') {
+        Some((kind, value)) => (kind.trim().to_lowercase(), value.trim()),
+        None => ("role".to_string(), text.trim()),
+    };
+    match kind.as_str() {
+        "role" | "group" if !value.is_empty() => Ok(Visibility {
+            kind,
+            value: value.to_string(),
+        }),
+        _ => Err(JiraError::Config(format!(
+            "--visibility expects role:NAME or group:NAME, got `{}`",
+            text
+        ))),
+    }
+}
+
+/// Author, date and id of each comment above its text rendered from Jira
+/// markup.
+fn render_comments(key: &str, comments: &[Comment], layout: TableLayout) -> String {
+    if comments.is_empty() {
+        return format!("No comments on {}\n", key);
+    }
+
+    let date = |date: &Option<String>| {
+        let date = date.as_deref().unwrap_or_default();
+        format_date(date, DATE_TIME_FORMAT).unwrap_or_else(|| date.to_string())
+    };
+    let mut lines = vec![];
+    for comment in comments {
+        let mut heading = vec![
+            styled(&Cell::bold(field_text(&comment.author)), layout),
+            date(&comment.created),
+            format!("#{}", comment.id),
+        ];
+        if comment.updated.is_some() && comment.updated != comment.created {
+            heading.push(format!("(edited {})", date(&comment.updated)));
+        }
+        if let Some(visibility) = &comment.visibility {
+            heading.push(format!("[{}]", visibility));
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(heading.join("  "));
+
+        let width = layout.width.map(|width| width.saturating_sub(2));
+        for line in render_markup(&comment.body, width, layout.color) {
+            lines.push(format!("  {}", line).trim_end().to_string());
+        }
+    }
+    lines.join("\n") + "\n"
+}
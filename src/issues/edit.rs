@@ -0,0 +1,409 @@
+use crate::commons::client::JiraClient;
+use crate::commons::custom_fields::{CustomFieldsCache, CustomFieldsHandler, CUSTOM_FIELD_PREFIX};
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::req_builder::build_put_req;
+use crate::commons::structs::{project_key, Issue};
+use crate::commons::table::field_text;
+use crate::commons::terminal::edit_text;
+use crate::issues::assign::assignee;
+use crate::issues::command_args::IssueEditOps;
+use crate::issues::issues_structs::{issue_url, EditRequest, IssueHandler, UpdateOperation};
+use serde_json::{json, Value};
+use serde_yaml::Mapping;
+use std::collections::BTreeMap;
+
+/// Fields offered in the editor, in that order, before the custom ones.
+static EDITABLE_FIELDS: [&str; 7] = [
+    "summary",
+    "description",
+    "labels",
+    "components",
+    "priority",
+    "assignee",
+    "duedate",
+];
+
+impl IssueHandler {
+    pub async fn edit(&self, options: &IssueEditOps, client: &JiraClient) -> JiraResult<()> {
+        let request = match options.has_changes() {
+            true => flags_request(options, client).await?,
+            false => self.editor_request(&options.key, client).await?,
+        };
+        if request.is_empty() {
+            println!("No changes to {}", &options.key);
+            return Ok(());
+        }
+        debug!(
+            "Edit request {}",
+            serde_json::to_string(&request).unwrap_or_default()
+        );
+
+        let url = issue_url(client, &options.key, &[])?;
+        client
+            .send(build_put_req(url, client).json(&request))
+            .await?;
+        println!("Updated {}", &options.key);
+        Ok(())
+    }
+
+    /// Opens the editable fields of the issue in `$EDITOR` as YAML and turns
+    /// the values changed into update operations.
+    async fn editor_request(&self, key: &str, client: &JiraClient) -> JiraResult<EditRequest> {
+        let issue = self.fetch(client, key).await?;
+        let custom_fields = match CustomFieldsHandler
+            .get_or_cache(client, project_key(key))
+            .await
+        {
+            Ok((custom_fields, _)) => custom_fields,
+            Err(e) => {
+                warn!("Custom fields of {} cannot be edited: {}", key, e);
+                CustomFieldsCache::new()
+            }
+        };
+
+        let (original, custom_ids) = editable_document(&issue, &custom_fields);
+        let edited = edit_in_editor(key, &original)?;
+
+        let mut request = EditRequest::default();
+        for (name, value) in edited.iter() {
+            let name = name.as_str().unwrap_or_default();
+            let before = original
+                .get(&serde_yaml::Value::from(name))
+                .ok_or_else(|| {
+                    JiraError::Config(format!("`{}` is not an editable field of {}", name, key))
+                })?;
+            if before == value {
+                continue;
+            }
+            let (before, after) = (to_json(before), to_json(value));
+            match custom_ids.get(name) {
+                Some(id) => request.push(id, UpdateOperation::Set(after)),
+                None if name == "assignee" && !after.is_null() => {
+                    let (reference, _) = assignee(client, key, &field_text(&after)).await?;
+                    request.push(name, UpdateOperation::Set(reference));
+                }
+                None => push_change(&mut request, name, &before, after),
+            }
+        }
+        Ok(request)
+    }
+}
+
+/// Update operations of the field flags.
+async fn flags_request(options: &IssueEditOps, client: &JiraClient) -> JiraResult<EditRequest> {
+    let mut request = EditRequest::default();
+    let set = |request: &mut EditRequest, field: &str, value: Value| {
+        request.push(field, UpdateOperation::Set(value))
+    };
+
+    if let Some(summary) = &options.summary {
+        set(&mut request, "summary", json!(summary));
+    }
+    if let Some(description) = &options.description {
+        set(&mut request, "description", json!(description));
+    }
+    if let Some(labels) = &options.labels {
+        set(&mut request, "labels", json!(labels));
+    }
+    for label in &options.add_labels {
+        request.push("labels", UpdateOperation::Add(json!(label)));
+    }
+    for label in &options.remove_labels {
+        request.push("labels", UpdateOperation::Remove(json!(label)));
+    }
+    if let Some(components) = &options.components {
+        let components = components.iter().map(|name| named(name)).collect();
+        set(&mut request, "components", Value::Array(components));
+    }
+    for component in &options.add_components {
+        request.push("components", UpdateOperation::Add(named(component)));
+    }
+    for component in &options.remove_components {
+        request.push("components", UpdateOperation::Remove(named(component)));
+    }
+    if let Some(priority) = &options.priority {
+        set(&mut request, "priority", named(priority));
+    }
+    if let Some(user) = &options.assignee {
+        let (reference, _) = assignee(client, &options.key, user).await?;
+        set(&mut request, "assignee", reference);
+    }
+    if let Some(due_date) = &options.due_date {
+        set(&mut request, "duedate", json!(due_date));
+    }
+
+    for (id, value) in field_flags(client, &options.key, &options.fields).await? {
+        set(&mut request, &id, value);
+    }
+    Ok(request)
+}
+
+/// Field ids and values of `--field "Name=value"` flags, names being
+/// resolved among the custom fields of every issue type of the project of
+/// `key`.
+pub(super) async fn field_flags(
+    client: &JiraClient,
+    key: &str,
+    fields: &[String],
+) -> JiraResult<Vec<(String, Value)>> {
+    let mut values = vec![];
+    for field in fields {
+        let (name, value) = field.split_once('=').ok_or_else(|| {
+            JiraError::Config(format!("--field expects Name=value, got `{}`", field))
+        })?;
+        let id = CustomFieldsHandler
+            .resolve_field(client, project_key(key), None, name)
+            .await?;
+        values.push((id, field_value(value.trim())));
+    }
+    Ok(values)
+}
+
+/// Operations turning the `before` value of a system field into `after`.
+/// Labels and components are diffed into `add` and `remove` operations.
+fn push_change(request: &mut EditRequest, field: &str, before: &Value, after: Value) {
+    match field {
+        "labels" | "components" => {
+            let names = |value: &Value| -> Vec<String> {
+                match value {
+                    Value::Array(values) => values.iter().map(field_text).collect(),
+                    Value::Null => vec![],
+                    value => vec![field_text(value)],
+                }
+            };
+            let (before, after) = (names(before), names(&after));
+            let value = |name: &String| match field {
+                "components" => named(name),
+                _ => json!(name),
+            };
+            for name in after.iter().filter(|name| !before.contains(name)) {
+                request.push(field, UpdateOperation::Add(value(name)));
+            }
+            for name in before.iter().filter(|name| !after.contains(name)) {
+                request.push(field, UpdateOperation::Remove(value(name)));
+            }
+        }
+        "priority" if !after.is_null() => {
+            request.push(field, UpdateOperation::Set(named(&field_text(&after))))
+        }
+        _ => request.push(field, UpdateOperation::Set(after)),
+    }
+}
+
+/// The editable fields of an issue, custom fields set on the issue by name,
+/// along with the id of each custom field name. A name shared by several
+/// fields is followed by the id, as in `Team (customfield_10030)`. Custom
+/// fields holding objects are left out.
+fn editable_document(
+    issue: &Issue,
+    custom_fields: &CustomFieldsCache,
+) -> (Mapping, BTreeMap<String, String>) {
+    let field = |id: &str| {
+        issue
+            .fields
+            .as_ref()
+            .and_then(|fields| fields.get(id))
+            .unwrap_or(Value::Null)
+    };
+    let mut document = Mapping::new();
+    for id in EDITABLE_FIELDS.iter() {
+        let value = match (*id, field(id)) {
+            ("labels", value) | ("components", value) => match value {
+                Value::Array(values) => values.iter().map(field_text).collect(),
+                _ => vec![],
+            }
+            .into(),
+            ("assignee", Value::Object(user)) => user
+                .get("name")
+                .or_else(|| user.get("accountId"))
+                .map(field_text)
+                .map(Value::from)
+                .unwrap_or(Value::Null),
+            (_, Value::Null) => Value::Null,
+            (_, value) => Value::from(field_text(&value)),
+        };
+        document.insert(serde_yaml::Value::from(*id), to_yaml(&value));
+    }
+
+    let is_shared = |name: &str| {
+        EDITABLE_FIELDS.contains(&name)
+            || custom_fields
+                .values()
+                .filter(|other| other.as_str() == name)
+                .count()
+                > 1
+    };
+    let mut custom = issue
+        .fields
+        .iter()
+        .flat_map(|fields| fields.unmapped_fields.iter())
+        .filter(|(id, value)| {
+            id.starts_with(CUSTOM_FIELD_PREFIX) && !field_text(value).is_empty() && is_plain(value)
+        })
+        .map(|(id, value)| {
+            let name = match custom_fields.get(id) {
+                Some(name) if is_shared(name) => format!("{} ({})", name, id),
+                Some(name) => name.clone(),
+                None => id.clone(),
+            };
+            (name, id.clone(), value.clone())
+        })
+        .collect::<Vec<(String, String, Value)>>();
+    custom.sort_by_key(|(name, _, _)| name.to_lowercase());
+
+    let mut custom_ids = BTreeMap::new();
+    for (name, id, value) in custom {
+        document.insert(serde_yaml::Value::from(name.as_str()), to_yaml(&value));
+        custom_ids.insert(name, id);
+    }
+    (document, custom_ids)
+}
+
+/// Opens the document in the editor and reads it back, a document left
+/// without any field meaning no change.
+fn edit_in_editor(key: &str, document: &Mapping) -> JiraResult<Mapping> {
+    let yaml = serde_yaml::to_string(document)
+        .map_err(|e| JiraError::deserialize("the issue fields", e))?;
+    let header = format!(
+        "# Editing {}. Change the values to update, lines starting with # are ignored.\n\
+         # Removing a field leaves it unchanged, set it to ~ to clear it.\n",
+        key
+    );
+    let file_name = format!("{}.yaml", key);
+    let edited = edit_text(&file_name, &(header + yaml.trim_start_matches("---\n")))?;
+    if edited
+        .lines()
+        .all(|line| line.trim().is_empty() || line.trim().starts_with('#'))
+    {
+        return Ok(Mapping::new());
+    }
+    serde_yaml::from_str::<Mapping>(&edited).map_err(|e| JiraError::deserialize(&file_name, e))
+}
+
+/// Values shown as is in the editor: scalars and lists of scalars.
+fn is_plain(value: &Value) -> bool {
+    match value {
+        Value::Object(_) => false,
+        Value::Array(values) => values
+            .iter()
+            .all(|value| !value.is_object() && !value.is_array()),
+        _ => true,
+    }
+}
+
+fn to_yaml(value: &Value) -> serde_yaml::Value {
+    serde_yaml::to_value(value).unwrap_or(serde_yaml::Value::Null)
+}
+
+fn to_json(value: &serde_yaml::Value) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// A `--field` value: JSON such as `5`, `["a","b"]` or `{"value":"x"}`, any
+/// other text being sent as a string.
+fn field_value(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| json!(text))
+}
+
+fn named(name: &str) -> Value {
+    json!({ "name": name })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    fn issue(fields: Value) -> Issue {
+        serde_json::from_value(json!({
+            "expand": "",
+            "id": "10042",
+            "key": "JC-42",
+            "self": "https://jira.example.com/rest/api/2/issue/10042",
+            "fields": fields
+        }))
+        .unwrap()
+    }
+
+    fn custom_fields(fields: &[(&str, &str)]) -> CustomFieldsCache {
+        fields
+            .iter()
+            .map(|(id, name)| (id.to_string(), name.to_string()))
+            .collect()
+    }
+
+    fn keys(document: &Mapping) -> Vec<String> {
+        document
+            .iter()
+            .map(|(key, _)| key.as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn only_custom_fields_set_on_the_issue_are_edited() {
+        let issue = issue(json!({
+            "summary": "Login",
+            "customfield_10016": 5,
+            "customfield_10017": null,
+            "customfield_10018": [],
+            "customfield_10019": { "value": "object" },
+            "customfield_10099": "unknown"
+        }));
+        let custom_fields = custom_fields(&[
+            ("customfield_10016", "Story Points"),
+            ("customfield_10017", "Flagged"),
+            ("customfield_10018", "Reviewers"),
+            ("customfield_10019", "Team"),
+        ]);
+
+        let (document, custom_ids) = editable_document(&issue, &custom_fields);
+        assert_eq!(
+            keys(&document)[EDITABLE_FIELDS.len()..],
+            ["customfield_10099", "Story Points"]
+        );
+        assert_eq!(custom_ids["Story Points"], "customfield_10016");
+        assert_eq!(custom_ids["customfield_10099"], "customfield_10099");
+    }
+
+    #[test]
+    fn shared_names_are_followed_by_the_field_id() {
+        let issue = issue(json!({
+            "customfield_10030": "Core",
+            "customfield_10031": "Web",
+            "customfield_10040": "free text"
+        }));
+        let custom_fields = custom_fields(&[
+            ("customfield_10030", "Team"),
+            ("customfield_10031", "Team"),
+            ("customfield_10040", "description"),
+        ]);
+
+        let (document, custom_ids) = editable_document(&issue, &custom_fields);
+        assert_eq!(
+            keys(&document)[EDITABLE_FIELDS.len()..],
+            [
+                "description (customfield_10040)",
+                "Team (customfield_10030)",
+                "Team (customfield_10031)"
+            ]
+        );
+        assert_eq!(custom_ids["Team (customfield_10031)"], "customfield_10031");
+        assert_eq!(
+            document[&serde_yaml::Value::from("description")],
+            serde_yaml::Value::Null
+        );
+    }
+
+    #[test]
+    fn replacing_and_changing_a_list_conflict() {
+        let parse = |args: &[&str]| {
+            IssueEditOps::from_iter_safe(["edit", "JC-42"].iter().chain(args)).map(|_| ())
+        };
+        assert!(parse(&["--labels", "a,b", "--add-label", "c"]).is_err());
+        assert!(parse(&["--labels", "a", "--remove-label", "c"]).is_err());
+        assert!(parse(&["--components", "api", "--add-component", "web"]).is_err());
+        assert!(parse(&["--components", "api", "--remove-component", "web"]).is_err());
+        assert!(parse(&["--add-label", "c", "--remove-label", "d"]).is_ok());
+        assert!(parse(&["--labels", "a", "--add-component", "web"]).is_ok());
+    }
+}
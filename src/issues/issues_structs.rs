@@ -0,0 +1,155 @@
+use crate::commons::client::JiraClient;
+use crate::commons::errors::JiraResult;
+use crate::commons::structs::REST_URI;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use url::Url;
+
+pub(crate) static ISSUE_URI: &str = "/issue";
+pub(crate) static BROWSE_URI: &str = "/browse";
+
+pub struct IssueHandler;
+
+/// Body of a `PUT /issue/{key}` changing fields through `update` operations.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EditRequest {
+    pub update: BTreeMap<String, Vec<UpdateOperation>>,
+}
+
+/// One `update` operation, serialized as `{"set": value}` and alike.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateOperation {
+    Set(Value),
+    Add(Value),
+    Remove(Value),
+}
+
+impl EditRequest {
+    pub fn push(&mut self, field: &str, operation: UpdateOperation) {
+        self.update
+            .entry(field.to_string())
+            .or_default()
+            .push(operation);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.update.is_empty()
+    }
+}
+
+/// Response of `GET /issue/{key}/transitions`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transitions {
+    pub transitions: Vec<Transition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transition {
+    pub id: String,
+    pub name: String,
+    /// Status the issue ends up in.
+    pub to: Value,
+    /// Fields of the transition screen, only returned when expanded.
+    #[serde(default)]
+    pub fields: BTreeMap<String, TransitionField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitionField {
+    #[serde(default)]
+    pub required: bool,
+    pub name: String,
+}
+
+/// Body of a `POST /issue/{key}/transitions`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransitionRequest {
+    pub transition: TransitionId,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransitionId {
+    pub id: String,
+}
+
+impl Transition {
+    /// Name of the status the transition leads to.
+    pub fn status(&self) -> &str {
+        self.to["name"].as_str().unwrap_or_default()
+    }
+}
+
+/// A user as returned by `/myself` and the user searches. Jira Cloud only
+/// identifies users by account id, Server by name.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    pub name: Option<String>,
+    pub account_id: Option<String>,
+    pub display_name: Option<String>,
+    pub email_address: Option<String>,
+}
+
+/// Response of `GET /serverInfo`, telling Cloud from Server and Data Center.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub deployment_type: Option<String>,
+}
+
+/// One page of `GET /issue/{key}/comment`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommentPage {
+    pub comments: Vec<Comment>,
+    pub total: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: String,
+    #[serde(default)]
+    pub author: Value,
+    #[serde(default)]
+    pub body: String,
+    pub created: Option<String>,
+    pub updated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+}
+
+/// Role or group a comment is restricted to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visibility {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: String,
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.kind, self.value)
+    }
+}
+
+/// Body of `POST /issue/{key}/comment` and `PUT /issue/{key}/comment/{id}`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentRequest {
+    pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+}
+
+/// URL of an issue resource, `segments` being appended after its key.
+pub fn issue_url(client: &JiraClient, key: &str, segments: &[&str]) -> JiraResult<Url> {
+    let mut url = Url::parse(&format!("{}{}{}", client.host(), REST_URI, ISSUE_URI))?;
+    url.path_segments_mut()
+        .map_err(|_| url::ParseError::RelativeUrlWithCannotBeABaseBase)?
+        .push(key)
+        .extend(segments);
+    Ok(url)
+}
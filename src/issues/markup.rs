@@ -0,0 +1,362 @@
+use unicode_width::UnicodeWidthChar;
+
+static BOLD: (&str, &str) = ("\x1b[1m", "\x1b[22m");
+static ITALIC: (&str, &str) = ("\x1b[3m", "\x1b[23m");
+static CODE: (&str, &str) = ("\x1b[36m", "\x1b[39m");
+static QUOTE_MARK: &str = "│ ";
+static BULLET: &str = "• ";
+static RULE_WIDTH: usize = 20;
+/// Name and closing tag of the blocks rendered verbatim.
+static BLOCKS: [(&str, &str); 2] = [("code", "{code}"), ("noformat", "{noformat}")];
+
+/// Renders Jira wiki markup for the terminal: headings and `*bold*` text are
+/// bolded, lists get bullets or numbers, `{code}` blocks are indented and links
+/// show their URL. Paragraphs wrap at `width` when given.
+pub fn render_markup(markup: &str, width: Option<usize>, color: bool) -> Vec<String> {
+    let style = |codes: (&'static str, &'static str)| if color { codes } else { ("", "") };
+    let mut lines = vec![];
+    // Closing tag of the block being rendered.
+    let mut block: Option<&str> = None;
+    let mut numbers: Vec<usize> = vec![];
+
+    for line in markup.lines() {
+        let mut line = line;
+        let mut tagged = false;
+        if block.is_none() {
+            if let Some((close, rest)) = block_start(line) {
+                block = Some(close);
+                line = rest;
+                tagged = true;
+            }
+        }
+        if let Some(close) = block {
+            let (code, rest) = match line.find(close) {
+                Some(end) => (&line[..end], Some(&line[end + close.len()..])),
+                None => (line, None),
+            };
+            // Lines holding a tag only show the code next to it.
+            if (!tagged && rest.is_none()) || !code.trim().is_empty() {
+                let (on, off) = style(CODE);
+                lines.push(format!("    {}{}{}", on, code.trim_end(), off));
+            }
+            match rest {
+                Some(rest) if !rest.trim().is_empty() => {
+                    block = None;
+                    line = rest;
+                }
+                Some(_) => {
+                    block = None;
+                    continue;
+                }
+                None => continue,
+            }
+        }
+
+        let trimmed = line.trim();
+        let list_marker = trimmed
+            .split_once(' ')
+            .filter(|(marker, _)| is_list_marker(marker));
+        if !matches!(list_marker, Some((marker, _)) if marker.starts_with('#')) {
+            numbers.clear();
+        }
+
+        let (prefix, text) = match list_marker {
+            Some((marker, text)) if marker.starts_with('#') => {
+                let depth = marker.len();
+                numbers.resize(depth, 0);
+                numbers[depth - 1] += 1;
+                let indent = "  ".repeat(depth - 1);
+                (format!("{}{}. ", indent, numbers[depth - 1]), text)
+            }
+            Some((marker, text)) => (format!("{}{}", "  ".repeat(marker.len() - 1), BULLET), text),
+            None => match heading(trimmed) {
+                Some(text) => {
+                    let (on, off) = style(BOLD);
+                    lines.push(format!("{}{}{}", on, inline(text, color), off));
+                    continue;
+                }
+                None if trimmed == "----" => {
+                    lines.push("─".repeat(RULE_WIDTH));
+                    continue;
+                }
+                None => match trimmed.strip_prefix("bq.") {
+                    Some(text) => (QUOTE_MARK.to_string(), text.trim()),
+                    None => (String::new(), trimmed),
+                },
+            },
+        };
+
+        lines.extend(wrap(&prefix, &inline(text, color), width));
+    }
+    lines
+}
+
+/// The closing tag of the `{code}` or `{noformat}` block a line opens, such as
+/// `{code:java}`, and the text following the opening tag.
+fn block_start(line: &str) -> Option<(&'static str, &str)> {
+    let line = line.trim_start().strip_prefix('{')?;
+    BLOCKS.iter().find_map(|(name, close)| {
+        let rest = line.strip_prefix(name)?;
+        let end = match rest.chars().next()? {
+            '}' => 0,
+            ':' => rest.find('}')?,
+            _ => return None,
+        };
+        Some((*close, &rest[end + 1..]))
+    })
+}
+
+fn is_list_marker(marker: &str) -> bool {
+    !marker.is_empty()
+        && (marker.chars().all(|c| c == '*') || marker.chars().all(|c| c == '#') || marker == "-")
+}
+
+/// Text of a `h1.` to `h6.` heading.
+fn heading(line: &str) -> Option<&str> {
+    let mut chars = line.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some('h'), Some('1'..='6'), Some('.')) => Some(line[3..].trim()),
+        _ => None,
+    }
+}
+
+/// Applies `*bold*`, `_italic_`, `{{monospace}}` and `[text|url]` links.
+fn inline(text: &str, color: bool) -> String {
+    let style = |codes: (&'static str, &'static str)| if color { codes } else { ("", "") };
+    let text = links(text);
+    let text = replace_pairs(&text, "{{", "}}", style(CODE));
+    let text = emphasis(&text, '*', style(BOLD));
+    emphasis(&text, '_', style(ITALIC))
+}
+
+/// `[text|url]` becomes `text (url)` and `[url]` becomes `url`.
+fn links(text: &str) -> String {
+    let mut rendered = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        let end = match rest[start..].find(']') {
+            Some(end) => start + end,
+            None => break,
+        };
+        rendered.push_str(&rest[..start]);
+        match rest[start + 1..end].split_once('|') {
+            Some((label, url)) => rendered.push_str(&format!("{} ({})", label, url)),
+            None => rendered.push_str(&rest[start + 1..end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn replace_pairs(text: &str, open: &str, close: &str, (on, off): (&str, &str)) -> String {
+    let mut rendered = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        let end = match rest[start + open.len()..].find(close) {
+            Some(end) => start + open.len() + end,
+            None => break,
+        };
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(on);
+        rendered.push_str(&rest[start + open.len()..end]);
+        rendered.push_str(off);
+        rest = &rest[end + close.len()..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Styles text between two `marker` characters placed at word boundaries.
+fn emphasis(text: &str, marker: char, (on, off): (&str, &str)) -> String {
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut rendered = String::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let opens = chars[index] == marker
+            && (index == 0 || !chars[index - 1].is_alphanumeric())
+            && chars.get(index + 1).is_some_and(|c| !c.is_whitespace());
+        let close = match opens {
+            true => (index + 2..chars.len()).find(|end| {
+                chars[*end] == marker
+                    && !chars[*end - 1].is_whitespace()
+                    && chars.get(*end + 1).map_or(true, |c| !c.is_alphanumeric())
+            }),
+            false => None,
+        };
+        match close {
+            Some(end) => {
+                rendered.push_str(on);
+                rendered.extend(&chars[index + 1..end]);
+                rendered.push_str(off);
+                index = end + 1;
+            }
+            None => {
+                rendered.push(chars[index]);
+                index += 1;
+            }
+        }
+    }
+    rendered
+}
+
+/// Word wraps `text` at `width`, the first line starting with `prefix` and the
+/// next ones indented as much.
+fn wrap(prefix: &str, text: &str, width: Option<usize>) -> Vec<String> {
+    let indent = " ".repeat(visible_width(prefix));
+    let width = match width {
+        Some(width) => width.saturating_sub(indent.len()).max(20),
+        None => return vec![format!("{}{}", prefix, text)],
+    };
+
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split(' ') {
+        if !line.is_empty() && visible_width(&line) + 1 + visible_width(word) > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| match index {
+            0 => format!("{}{}", prefix, line),
+            _ => format!("{}{}", indent, line),
+        })
+        .collect()
+}
+
+/// Terminal width of text, skipping ANSI escape sequences.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match (in_escape, c) {
+            (false, '\x1b') => in_escape = true,
+            (true, 'm') => in_escape = false,
+            (true, _) => {}
+            (false, c) => width += c.width().unwrap_or(0),
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(markup: &str) -> Vec<String> {
+        render_markup(markup, None, false)
+    }
+
+    #[test]
+    fn headings_and_rules() {
+        assert_eq!(
+            plain("h1. Title\nh3.  Sub *part*"),
+            vec!["Title", "Sub part"]
+        );
+        assert_eq!(
+            render_markup("h2. Title", None, true),
+            vec!["\x1b[1mTitle\x1b[22m"]
+        );
+        assert_eq!(plain("----"), vec!["─".repeat(RULE_WIDTH)]);
+        assert_eq!(plain("h7. Not a heading"), vec!["h7. Not a heading"]);
+    }
+
+    #[test]
+    fn bullet_lists() {
+        assert_eq!(
+            plain("* one\n** nested\n- dash"),
+            vec!["• one", "  • nested", "• dash"]
+        );
+        assert_eq!(plain("*bold* text"), vec!["bold text"]);
+    }
+
+    #[test]
+    fn numbered_lists_restart_after_other_lines() {
+        assert_eq!(
+            plain("# one\n## sub\n## sub\n# two\ntext\n# again"),
+            vec!["1. one", "  1. sub", "  2. sub", "2. two", "text", "1. again"]
+        );
+    }
+
+    #[test]
+    fn emphasis_at_word_boundaries() {
+        let styled = |markup| inline(markup, true);
+        assert_eq!(styled("a *bold* word"), "a \x1b[1mbold\x1b[22m word");
+        assert_eq!(styled("_italic_."), "\x1b[3mitalic\x1b[23m.");
+        assert_eq!(styled("{{x = 1}}"), "\x1b[36mx = 1\x1b[39m");
+        assert_eq!(
+            styled("2*3*4 and snake_case_name"),
+            "2*3*4 and snake_case_name"
+        );
+        assert_eq!(styled("* not bold *"), "* not bold *");
+    }
+
+    #[test]
+    fn links_show_their_url() {
+        assert_eq!(
+            plain("See [the docs|https://example.com] or [https://jira.example.com]."),
+            vec!["See the docs (https://example.com) or https://jira.example.com."]
+        );
+        assert_eq!(plain("[unclosed"), vec!["[unclosed"]);
+    }
+
+    #[test]
+    fn code_blocks_are_indented_verbatim() {
+        assert_eq!(
+            plain("before\n{code:java}\nint *a* = 1;\n\n  return;\n{code}\nafter *b*"),
+            vec![
+                "before",
+                "    int *a* = 1;",
+                "    ",
+                "      return;",
+                "after b"
+            ]
+        );
+        assert_eq!(
+            plain("{noformat}\nh1. raw\n{noformat}"),
+            vec!["    h1. raw"]
+        );
+    }
+
+    #[test]
+    fn blocks_on_one_line() {
+        assert_eq!(
+            plain("{code}let x = 1;{code}\nh1. Next"),
+            vec!["    let x = 1;", "Next"]
+        );
+        assert_eq!(
+            plain("{noformat:nopanel=true}raw *text*{noformat} then *bold*\n* item"),
+            vec!["    raw *text*", "then bold", "• item"]
+        );
+    }
+
+    #[test]
+    fn blocks_opening_or_closing_next_to_code() {
+        assert_eq!(
+            plain("{code}first\nsecond{code}\ndone"),
+            vec!["    first", "    second", "done"]
+        );
+        assert_eq!(plain("{codex} is text"), vec!["{codex} is text"]);
+    }
+
+    #[test]
+    fn paragraphs_wrap_under_their_prefix() {
+        assert_eq!(
+            render_markup("* one two three four five six", Some(22), false),
+            vec!["• one two three four", "  five six"]
+        );
+        assert_eq!(
+            render_markup("bq. quoted text", None, false),
+            vec!["│ quoted text"]
+        );
+    }
+}
@@ -0,0 +1,8 @@
+mod assign;
+pub mod command_args;
+mod comment;
+mod edit;
+pub mod issues_structs;
+mod markup;
+mod transition;
+mod view;
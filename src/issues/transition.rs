@@ -0,0 +1,309 @@
+use crate::commons::client::JiraClient;
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::output::{Listing, RenderOptions};
+use crate::commons::req_builder::{build_get_req, build_post_req};
+use crate::commons::search::IssueSearch;
+use crate::commons::structs::STDIN;
+use crate::issues::command_args::{IssueMoveOps, IssueViewOps};
+use crate::issues::edit::field_flags;
+use crate::issues::issues_structs::{
+    issue_url, IssueHandler, Transition, TransitionId, TransitionRequest, Transitions,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+impl IssueHandler {
+    pub async fn transitions(
+        &self,
+        options: &IssueViewOps,
+        client: &JiraClient,
+        render: &RenderOptions,
+    ) -> JiraResult<()> {
+        let transitions = fetch_transitions(client, &options.key).await?;
+
+        let mut listing = Listing::new(&["id", "name", "status", "required fields"]);
+        for transition in transitions {
+            let source = serde_json::to_value(&transition).unwrap_or_default();
+            let required = transition
+                .fields
+                .values()
+                .filter(|field| field.required)
+                .map(|field| field.name.clone())
+                .collect::<Vec<String>>();
+            listing.rows.push(vec![
+                Value::from(transition.id),
+                Value::from(transition.name),
+                transition.to,
+                Value::from(required),
+            ]);
+            listing.sources.push(source);
+        }
+
+        listing.print(render)
+    }
+
+    /// Moves one issue, or every issue read from stdin or found by `--jql`.
+    /// Issues of a bulk move that fail are reported and skipped.
+    pub async fn move_issues(&self, options: &IssueMoveOps, client: &JiraClient) -> JiraResult<()> {
+        let keys = issue_keys(options, client).await?;
+        match keys.as_slice() {
+            [] => return Err(JiraError::Config("no issue to move".to_string())),
+            [key] => return move_issue(client, key, options).await,
+            _ => (),
+        }
+
+        let mut moved = 0;
+        for key in &keys {
+            match move_issue(client, key, options).await {
+                Ok(()) => moved += 1,
+                Err(e) => error!("{} was not moved: {}", key, e),
+            }
+        }
+        match moved == keys.len() {
+            true => Ok(()),
+            false => Err(JiraError::PartialFailure {
+                succeeded: moved,
+                total: keys.len(),
+                what: "issues moved",
+            }),
+        }
+    }
+}
+
+async fn move_issue(client: &JiraClient, key: &str, options: &IssueMoveOps) -> JiraResult<()> {
+    let transitions = fetch_transitions(client, key).await?;
+    let transition = find_transition(&transitions, options.transition(), key)?;
+
+    let mut fields = BTreeMap::new();
+    if let Some(resolution) = &options.resolution {
+        fields.insert("resolution".to_string(), json!({ "name": resolution }));
+    }
+    fields.extend(field_flags(client, key, &options.fields).await?);
+
+    let request = TransitionRequest {
+        transition: TransitionId {
+            id: transition.id.clone(),
+        },
+        fields,
+    };
+    debug!(
+        "Transition request {}",
+        serde_json::to_string(&request).unwrap_or_default()
+    );
+
+    let url = issue_url(client, key, &["transitions"])?;
+    client
+        .send(build_post_req(url, client).json(&request))
+        .await?;
+    println!("Moved {} to {}", key, transition.status());
+    Ok(())
+}
+
+/// Transitions available to an issue, along with their screen fields.
+async fn fetch_transitions(client: &JiraClient, key: &str) -> JiraResult<Vec<Transition>> {
+    let mut url = issue_url(client, key, &["transitions"])?;
+    url.query_pairs_mut()
+        .append_pair("expand", "transitions.fields");
+    debug!("Fetching transitions {}", url);
+    let response = client
+        .send_json::<Transitions>(build_get_req(url, client))
+        .await?;
+    Ok(response.transitions)
+}
+
+/// The transition whose name or target status best matches `wanted`: the
+/// same name first, ignoring case and punctuation, then names starting with
+/// it, then containing it, then containing its letters in order. Several
+/// transitions matching equally well are an error.
+fn find_transition<'a>(
+    transitions: &'a [Transition],
+    wanted: &str,
+    key: &str,
+) -> JiraResult<&'a Transition> {
+    let wanted_name = normalize(wanted);
+    let rank = |transition: &Transition| {
+        [transition.name.as_str(), transition.status()]
+            .iter()
+            .map(|name| normalize(name))
+            .filter_map(|name| match () {
+                _ if name == wanted_name || transition.id == wanted => Some(0),
+                _ if name.starts_with(&wanted_name) => Some(1),
+                _ if name.contains(&wanted_name) => Some(2),
+                _ if is_subsequence(&wanted_name, &name) => Some(3),
+                _ => None,
+            })
+            .min()
+    };
+
+    let ranked = transitions
+        .iter()
+        .filter_map(|transition| rank(transition).map(|rank| (rank, transition)))
+        .collect::<Vec<(usize, &Transition)>>();
+    let best = ranked.iter().map(|(rank, _)| *rank).min();
+    let matches = ranked
+        .into_iter()
+        .filter(|(rank, _)| Some(*rank) == best)
+        .map(|(_, transition)| transition)
+        .collect::<Vec<&Transition>>();
+
+    match matches.as_slice() {
+        [transition] => Ok(transition),
+        [] => Err(JiraError::Config(format!(
+            "no transition of {} matches `{}`, available: {}",
+            key,
+            wanted,
+            describe(transitions.iter())
+        ))),
+        _ => Err(JiraError::Config(format!(
+            "`{}` matches several transitions of {}: {}",
+            wanted,
+            key,
+            describe(matches.into_iter())
+        ))),
+    }
+}
+
+fn describe<'a>(transitions: impl Iterator<Item = &'a Transition>) -> String {
+    transitions
+        .map(|transition| format!("{} ({})", transition.name, transition.status()))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn is_subsequence(wanted: &str, name: &str) -> bool {
+    let mut chars = name.chars();
+    wanted.chars().all(|c| chars.any(|other| other == c))
+}
+
+/// Keys to move: the arguments, the issues found by `--jql`, or the issue
+/// keys found in stdin, e.g. the output of `search -o tsv --columns key`.
+async fn issue_keys(options: &IssueMoveOps, client: &JiraClient) -> JiraResult<Vec<String>> {
+    match (&options.jql, options.keys()) {
+        (Some(_), [_, ..]) => Err(JiraError::Config(
+            "issue keys and --jql cannot be used together".to_string(),
+        )),
+        (Some(jql), []) => {
+            let result = IssueSearch::new(client, jql).columns(&[]).collect().await?;
+            Ok(result.issues.into_iter().map(|issue| issue.key).collect())
+        }
+        (None, []) => read_keys(),
+        (None, [key]) if key == STDIN => read_keys(),
+        (None, keys) => Ok(keys.to_vec()),
+    }
+}
+
+fn read_keys() -> JiraResult<Vec<String>> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| JiraError::io("stdin", e))?;
+    Ok(input
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '"')
+        .filter(|word| is_issue_key(word))
+        .map(str::to_string)
+        .collect())
+}
+
+fn is_issue_key(word: &str) -> bool {
+    match word.rsplit_once('-') {
+        Some((project, number)) => {
+            project.starts_with(|c: char| c.is_ascii_uppercase())
+                && project
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+                && !number.is_empty()
+                && number.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transitions() -> Vec<Transition> {
+        [
+            ("11", "Start Progress", "In Progress"),
+            ("21", "Stop Progress", "To Do"),
+            ("31", "Resolve", "Done"),
+            ("41", "Close Issue", "Closed"),
+            ("51", "Reopen", "Reopened"),
+        ]
+        .iter()
+        .map(|(id, name, status)| Transition {
+            id: id.to_string(),
+            name: name.to_string(),
+            to: json!({ "name": status }),
+            fields: BTreeMap::new(),
+        })
+        .collect()
+    }
+
+    fn found(wanted: &str) -> String {
+        let transitions = transitions();
+        find_transition(&transitions, wanted, "JC-1")
+            .map(|transition| transition.id.clone())
+            .unwrap_or_else(|e| e.to_string())
+    }
+
+    #[test]
+    fn exact_name_status_or_id() {
+        assert_eq!(found("Resolve"), "31");
+        assert_eq!(found("resolve"), "31");
+        assert_eq!(found("done"), "31");
+        assert_eq!(found("in-progress"), "11");
+        assert_eq!(found("  To Do "), "21");
+        assert_eq!(found("41"), "41");
+    }
+
+    #[test]
+    fn exact_match_beats_prefix() {
+        // `Reopen` is exact while `Reopened` only starts with it.
+        assert_eq!(found("reopen"), "51");
+    }
+
+    #[test]
+    fn prefix_then_contains_then_subsequence() {
+        assert_eq!(found("reso"), "31");
+        assert_eq!(found("start"), "11");
+        assert_eq!(found("issue"), "41");
+        assert_eq!(found("clss"), "41");
+    }
+
+    #[test]
+    fn ambiguous_matches_are_listed() {
+        let error = found("progress");
+        assert!(error.contains("`progress` matches several transitions of JC-1"));
+        assert!(error.contains("Start Progress (In Progress)"));
+        assert!(error.contains("Stop Progress (To Do)"));
+        assert!(!error.contains("Resolve"));
+    }
+
+    #[test]
+    fn no_match_lists_every_transition() {
+        let error = found("deploy");
+        assert!(error.contains("no transition of JC-1 matches `deploy`"));
+        assert!(error.contains("Resolve (Done)"));
+        assert!(error.contains("Reopen (Reopened)"));
+    }
+
+    #[test]
+    fn issue_keys() {
+        assert!(is_issue_key("JC-42"));
+        assert!(is_issue_key("MY_PROJ2-7"));
+        assert!(!is_issue_key("jc-42"));
+        assert!(!is_issue_key("JC-"));
+        assert!(!is_issue_key("JC-4a"));
+        assert!(!is_issue_key("2JC-4"));
+        assert!(!is_issue_key("Done"));
+    }
+}
@@ -0,0 +1,318 @@
+use crate::commons::client::JiraClient;
+use crate::commons::custom_fields::{CustomFieldsCache, CustomFieldsHandler};
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::output::{serialize, Listing, OutputFormat, RenderOptions};
+use crate::commons::req_builder::build_get_req;
+use crate::commons::structs::{project_key, Issue, DATE_TIME_FORMAT};
+use crate::commons::table::{field_text, styled, Cell, TableLayout};
+use crate::commons::template::format_date;
+use crate::commons::terminal::write_paged;
+use crate::issues::command_args::IssueViewOps;
+use crate::issues::issues_structs::{issue_url, IssueHandler, BROWSE_URI};
+use crate::issues::markup::render_markup;
+use serde_json::Value;
+
+static EPIC_LINK: &str = "Epic Link";
+
+/// Labels and ids of the system fields shown above the custom ones.
+static SYSTEM_FIELDS: [(&str, &str); 9] = [
+    ("Status", "status"),
+    ("Type", "issuetype"),
+    ("Priority", "priority"),
+    ("Assignee", "assignee"),
+    ("Reporter", "reporter"),
+    ("Labels", "labels"),
+    ("Components", "components"),
+    ("Fix versions", "fixVersions"),
+    ("Resolution", "resolution"),
+];
+
+impl IssueHandler {
+    pub async fn view(
+        &self,
+        options: &IssueViewOps,
+        client: &JiraClient,
+        render: &RenderOptions,
+    ) -> JiraResult<()> {
+        let issue = self.fetch(client, &options.key).await?;
+        let project = issue_project(&issue);
+        let custom_fields = match CustomFieldsHandler.get_or_cache(client, &project).await {
+            Ok((custom_fields, _)) => custom_fields,
+            Err(e) => {
+                warn!(
+                    "Custom field names of project {} unavailable: {}",
+                    project, e
+                );
+                CustomFieldsCache::new()
+            }
+        };
+
+        if render.template.is_some() {
+            let columns = render.columns("view", &[], &[]);
+            let columns = CustomFieldsHandler
+                .resolve_columns(client, Some(&project), &columns)
+                .await?;
+            return Listing::from_issues(&[issue], &columns).print(render);
+        }
+
+        let text = match render.format {
+            OutputFormat::Table => {
+                render_issue(&issue, &custom_fields, client.host(), render.layout)
+            }
+            OutputFormat::Json | OutputFormat::Yaml => serialize(&issue, render.format)?,
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let mut listing = Listing::new(&["field", "value"]);
+                for (name, value) in detail_rows(&issue, &custom_fields) {
+                    listing.rows.push(vec![Value::from(name), value]);
+                }
+                listing.render(render)?
+            }
+        };
+        write_paged(&text, render.pager).map_err(|e| JiraError::io("stdout", e))
+    }
+
+    /// Fetches an issue with all its fields.
+    pub async fn fetch(&self, client: &JiraClient, key: &str) -> JiraResult<Issue> {
+        let url = issue_url(client, key, &[])?;
+        debug!("Fetching issue {}", url);
+        client
+            .send_json::<Issue>(build_get_req(url, client))
+            .await
+            .map_err(|e| match e {
+                JiraError::NotFound(_) => JiraError::NotFound(format!("issue {}", key)),
+                e => e,
+            })
+    }
+}
+
+/// Key of the project of an issue, read from its key when the `project`
+/// field was not returned.
+fn issue_project(issue: &Issue) -> String {
+    issue
+        .fields
+        .as_ref()
+        .and_then(|fields| fields.get("project"))
+        .and_then(|project| project.get("key")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| project_key(&issue.key).to_string())
+}
+
+fn field(issue: &Issue, id: &str) -> Value {
+    issue
+        .fields
+        .as_ref()
+        .and_then(|fields| fields.get(id))
+        .unwrap_or(Value::Null)
+}
+
+/// Every non empty field as a label and value, custom fields by name.
+fn detail_rows(issue: &Issue, custom_fields: &CustomFieldsCache) -> Vec<(String, Value)> {
+    let mut rows = vec![
+        ("Key".to_string(), Value::from(issue.key.clone())),
+        ("Summary".to_string(), field(issue, "summary")),
+    ];
+    for (label, id) in SYSTEM_FIELDS.iter() {
+        rows.push((label.to_string(), field(issue, id)));
+    }
+    rows.push(("Parent".to_string(), parent(issue)));
+    for (label, id) in [("Created", "created"), ("Updated", "updated")] {
+        rows.push((label.to_string(), date(&field(issue, id))));
+    }
+    rows.extend(custom_rows(issue, custom_fields));
+    rows.push(("Description".to_string(), field(issue, "description")));
+    rows.push(("Links".to_string(), Value::from(issue_links(issue))));
+    rows.push(("Sub-tasks".to_string(), Value::from(subtasks(issue))));
+
+    rows.into_iter()
+        .filter(|(_, value)| !field_text(value).is_empty())
+        .collect()
+}
+
+/// The parent of a sub-task or next-gen issue, else its epic.
+fn parent(issue: &Issue) -> Value {
+    match field(issue, "parent") {
+        Value::Null => Value::Null,
+        parent => Value::from(linked_issue(&parent)),
+    }
+}
+
+/// Custom fields with a value, sorted by name. Fields missing from the
+/// custom fields cache keep their id.
+fn custom_rows(issue: &Issue, custom_fields: &CustomFieldsCache) -> Vec<(String, Value)> {
+    let mut rows = issue
+        .fields
+        .iter()
+        .flat_map(|fields| fields.unmapped_fields.iter())
+        .filter(|(id, value)| id.starts_with("customfield_") && !field_text(value).is_empty())
+        .map(|(id, value)| {
+            let name = custom_fields.get(id).cloned().unwrap_or_else(|| id.clone());
+            (name, sprint_names(value))
+        })
+        .collect::<Vec<(String, Value)>>();
+    rows.sort_by_key(|(name, _)| name.to_lowercase());
+    rows
+}
+
+/// Jira Server returns sprints as `...Sprint@1a2b[id=3,name=Sprint 3,...]`.
+fn sprint_names(value: &Value) -> Value {
+    let name = |text: &str| -> Option<String> {
+        let start = text.find(",name=")? + ",name=".len();
+        let end = text[start..]
+            .find(',')
+            .map_or(text.len(), |end| start + end);
+        Some(text[start..end].to_string())
+    };
+    match value {
+        Value::Array(values) => Value::Array(values.iter().map(sprint_names).collect()),
+        Value::String(text) if text.contains("Sprint@") => {
+            name(text).map_or_else(|| value.clone(), Value::from)
+        }
+        value => value.clone(),
+    }
+}
+
+fn date(value: &Value) -> Value {
+    match value
+        .as_str()
+        .and_then(|text| format_date(text, DATE_TIME_FORMAT))
+    {
+        Some(date) => Value::from(date),
+        None => value.clone(),
+    }
+}
+
+/// `KEY summary (status)` of a linked issue, sub-task or parent.
+fn linked_issue(issue: &Value) -> String {
+    let key = issue.get("key").map(field_text).unwrap_or_default();
+    let summary = issue
+        .pointer("/fields/summary")
+        .map(field_text)
+        .unwrap_or_default();
+    match issue.pointer("/fields/status").map(field_text) {
+        Some(status) if !status.is_empty() => format!("{} {} ({})", key, summary, status),
+        _ => format!("{} {}", key, summary).trim_end().to_string(),
+    }
+}
+
+fn render_issue(
+    issue: &Issue,
+    custom_fields: &CustomFieldsCache,
+    host: &str,
+    layout: TableLayout,
+) -> String {
+    let bold = |text: String| styled(&Cell::bold(text), layout);
+    let mut lines = vec![
+        bold(format!(
+            "{}  {}",
+            issue.key,
+            field_text(&field(issue, "summary"))
+        )),
+        format!("{}{}/{}", host.trim_end_matches('/'), BROWSE_URI, issue.key),
+        String::new(),
+    ];
+
+    let epic_link = custom_fields
+        .iter()
+        .find(|(_, name)| name.as_str() == EPIC_LINK)
+        .map(|(id, _)| field(issue, id))
+        .unwrap_or(Value::Null);
+    let mut details = SYSTEM_FIELDS
+        .iter()
+        .map(|(label, id)| (label.to_string(), Cell::field(id, &field(issue, id))))
+        .collect::<Vec<(String, Cell)>>();
+    for (label, value) in [
+        ("Parent", parent(issue)),
+        ("Epic", epic_link),
+        ("Created", date(&field(issue, "created"))),
+        ("Updated", date(&field(issue, "updated"))),
+    ] {
+        details.push((label.to_string(), Cell::field(label, &value)));
+    }
+    lines.extend(aligned(details, layout, ""));
+
+    let custom = custom_rows(issue, custom_fields)
+        .into_iter()
+        .filter(|(name, _)| name != EPIC_LINK)
+        .map(|(name, value)| (name, Cell::field("", &value)))
+        .collect::<Vec<(String, Cell)>>();
+    if !custom.is_empty() {
+        lines.push(String::new());
+        lines.push(bold("Custom fields".to_string()));
+        lines.extend(aligned(custom, layout, "  "));
+    }
+
+    if let Some(description) = field(issue, "description").as_str() {
+        lines.push(String::new());
+        lines.push(bold("Description".to_string()));
+        let width = layout.width.map(|width| width.saturating_sub(2));
+        for line in render_markup(description, width, layout.color) {
+            lines.push(format!("  {}", line).trim_end().to_string());
+        }
+    }
+
+    for (heading, issues) in [
+        ("Links", issue_links(issue)),
+        ("Sub-tasks", subtasks(issue)),
+    ] {
+        if !issues.is_empty() {
+            lines.push(String::new());
+            lines.push(bold(heading.to_string()));
+            lines.extend(issues.iter().map(|issue| format!("  {}", issue)));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// `relation KEY summary (status)` of each issue linked to an issue.
+fn issue_links(issue: &Issue) -> Vec<String> {
+    field(issue, "issuelinks")
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|link| {
+            let (relation, other) = match (link.get("outwardIssue"), link.get("inwardIssue")) {
+                (Some(other), _) => (link.pointer("/type/outward"), other),
+                (None, Some(other)) => (link.pointer("/type/inward"), other),
+                (None, None) => return None,
+            };
+            let relation = relation.map(field_text).unwrap_or_default();
+            Some(format!("{} {}", relation, linked_issue(other)))
+        })
+        .collect()
+}
+
+fn subtasks(issue: &Issue) -> Vec<String> {
+    field(issue, "subtasks")
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(linked_issue)
+        .collect()
+}
+
+/// `Label:  value` lines of the non empty values, labels padded to align.
+fn aligned(rows: Vec<(String, Cell)>, layout: TableLayout, indent: &str) -> Vec<String> {
+    let rows = rows
+        .into_iter()
+        .filter(|(_, cell)| !cell.text.is_empty())
+        .collect::<Vec<(String, Cell)>>();
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+
+    rows.iter()
+        .map(|(label, cell)| {
+            format!(
+                "{}{:width$}  {}",
+                indent,
+                format!("{}:", label),
+                styled(cell, layout),
+                width = width
+            )
+        })
+        .collect()
+}
@@ -1,28 +1,38 @@
-#![feature(associated_type_defaults)]
-#![feature(default_free_fn)]
-#![feature(option_insert)]
 #[macro_use]
 extern crate log;
 
 mod commons;
+mod config;
 mod epics;
+mod issues;
 mod projects;
+mod search;
 mod stories;
 
 extern crate dirs;
 extern crate pretty_env_logger;
 
+use crate::config::command_args::ConfigOps;
+use crate::config::config_structs::ConfigHandler;
 use crate::epics::command_args::EpicOps;
 use crate::epics::epics_projects::EpicHandler;
+use crate::issues::command_args::IssueOps;
+use crate::issues::issues_structs::IssueHandler;
 use crate::projects::command_args::ProjectOps;
 use crate::projects::projects_structs::ProjectHandler;
+use crate::search::command_args::SearchOps;
+use crate::search::search_structs::SearchHandler;
 use crate::stories::stories_structs::StoriesHandler;
-use commons::{file_utilities::load_yaml, structs::AuthOptions};
+use commons::config::{load_profile, ConnectionFlags, ResolvedProfile};
+use commons::errors::{JiraError, JiraResult};
+use commons::output::{OutputFormat, RenderOptions};
+use commons::table::TableLayout;
+use commons::template::Template;
+use commons::terminal;
 use std::env;
+use std::process;
 use stories::command_args::{StoryListOps, StoryOps};
 use structopt::StructOpt;
-use yaml_rust::YamlLoader;
-use dirs::home_dir;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "jira-cli")]
@@ -31,6 +41,44 @@ pub struct Opts {
     #[structopt(short = "l", long = "log", default_value = "INFO")]
     log_level: String,
 
+    /// Connection profile from conf.yaml to use
+    #[structopt(long = "profile", env = "JIRA_CLI_PROFILE")]
+    profile: Option<String>,
+
+    /// Jira host, overrides the profile `host`
+    #[structopt(long = "host", env = "JIRA_CLI_HOST")]
+    host: Option<String>,
+
+    /// Jira user or Cloud account email, overrides the profile `user`
+    #[structopt(long = "user", env = "JIRA_CLI_USER")]
+    user: Option<String>,
+
+    /// API token, personal access token or password, overrides the profile secret
+    #[structopt(long = "token", env = "JIRA_CLI_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Output format of list and search commands
+    #[structopt(
+        short = "o",
+        long = "output",
+        default_value = "table",
+        possible_values = &OutputFormat::VARIANTS,
+        case_insensitive = true
+    )]
+    output: OutputFormat,
+
+    /// Template printed once per result, e.g. '{key}\t{status|upper}\t{summary|truncate:60}'
+    #[structopt(long = "format")]
+    format: Option<String>,
+
+    /// Disables colors, also disabled by a non empty NO_COLOR
+    #[structopt(long = "no-color")]
+    no_color: bool,
+
+    /// Prints long output directly instead of through $PAGER
+    #[structopt(long = "no-pager")]
+    no_pager: bool,
+
     /// SUBCOMMANDS
     #[structopt(subcommand)]
     commands: Option<Commands>,
@@ -44,6 +92,15 @@ enum Commands {
     /// Add jira objects, currently supported [Stories]
     #[structopt(name = "add")]
     Add(Add),
+    /// View single issues
+    #[structopt(name = "issue")]
+    Issue(IssueOps),
+    /// Search issues with a JQL query
+    #[structopt(name = "search")]
+    Search(SearchOps),
+    /// Initialise, validate and display the configuration
+    #[structopt(name = "config")]
+    Config(ConfigOps),
 }
 
 #[derive(StructOpt, Debug)]
@@ -70,39 +127,74 @@ async fn main() {
     env::set_var("RUST_LOG", opts.log_level.to_ascii_uppercase());
     pretty_env_logger::init();
 
-    let home_dir = home_dir().unwrap();
-    let conf_path = format!("{}{}", home_dir.to_str().unwrap(), "/.jira-cli/conf.yaml");
-    println!("{}", &conf_path);
-    let conf_string = load_yaml(&conf_path).await.unwrap();
+    let flags = ConnectionFlags {
+        profile: opts.profile.clone(),
+        host: opts.host.clone(),
+        user: opts.user.clone(),
+        token: opts.token.clone(),
+    };
+
+    let result = match &opts.commands {
+        Some(Commands::Config(args)) => handle_config(args, &flags).await,
+        _ => match load_profile(&flags).await {
+            Ok(profile) => handle_args(opts, &profile).await,
+            Err(e) => Err(e),
+        },
+    };
 
-    let conf = &YamlLoader::load_from_str(&conf_string).unwrap()[0];
-    let auth_options = AuthOptions {
-        host: conf["jira"]["host"].as_str().unwrap().to_owned(),
-        user: Some(conf["jira"]["user"].as_str().unwrap().to_owned()),
-        pass: Some(conf["jira"]["password"].as_str().unwrap().to_owned()),
+    if let Err(e) = result {
+        error!("{}", e);
+        process::exit(e.exit_code());
+    }
+}
+
+async fn handle_config(args: &ConfigOps, flags: &ConnectionFlags) -> JiraResult<()> {
+    let result = match args {
+        ConfigOps::Init(args) => ConfigHandler.init(args).await,
+        ConfigOps::Validate => ConfigHandler.validate().await,
+        ConfigOps::Show => ConfigHandler.show(flags).await,
     };
-    handle_args(opts, &auth_options).await;
+    result.map_err(|e| JiraError::Config(e.to_string()))
 }
 
-async fn handle_args(opts: Opts, auth_options: &AuthOptions) {
+async fn handle_args(opts: Opts, profile: &ResolvedProfile) -> JiraResult<()> {
+    let client = &profile.client()?;
+    let render = RenderOptions {
+        format: opts.output,
+        default_columns: profile.columns.clone(),
+        template: opts.format.as_deref().map(Template::parse).transpose()?,
+        layout: TableLayout {
+            width: terminal::size().map(|(columns, _)| columns),
+            color: terminal::color_enabled(opts.no_color),
+        },
+        pager: !opts.no_pager,
+    };
     if let Some(subcommand) = opts.commands {
         match subcommand {
             Commands::List(issue_type) => match issue_type {
-                List::Story(args) => {
-                    StoriesHandler.list(&args, auth_options).await;
-                }
-                List::Epic(args) => {
-                    EpicHandler.list(&args, auth_options).await;
-                }
-                List::Project(args) => {
-                    ProjectHandler.list(&args, auth_options).await;
-                }
+                List::Story(args) => StoriesHandler.list(&args, client, &render).await?,
+                List::Epic(args) => EpicHandler.list(&args, client, &render).await?,
+                List::Project(args) => ProjectHandler.list(&args, client, &render).await?,
             },
             Commands::Add(issue_type) => match issue_type {
-                Add::Story(args) => {
-                    StoriesHandler.create_story(&args, auth_options).await;
+                Add::Story(args) => StoriesHandler.create_story(&args, client).await?,
+            },
+            Commands::Issue(issue_ops) => match issue_ops {
+                IssueOps::View(args) => IssueHandler.view(&args, client, &render).await?,
+                IssueOps::Edit(args) => IssueHandler.edit(&args, client).await?,
+                IssueOps::Transitions(args) => {
+                    IssueHandler.transitions(&args, client, &render).await?
+                }
+                IssueOps::Move(args) => IssueHandler.move_issues(&args, client).await?,
+                IssueOps::Assign(args) => IssueHandler.assign(&args, client).await?,
+                IssueOps::Unassign(args) => IssueHandler.unassign(&args, client).await?,
+                IssueOps::Comment(comment_ops) => {
+                    IssueHandler.comment(comment_ops, client, &render).await?
                 }
             },
+            Commands::Search(args) => SearchHandler.search(&args, client, &render).await?,
+            Commands::Config(_) => {}
         }
     }
+    Ok(())
 }
@@ -1,59 +1,40 @@
+use crate::commons::client::JiraClient;
+use crate::commons::errors::JiraResult;
+use crate::commons::output::{Listing, RenderOptions};
 use crate::commons::req_builder::build_get_req;
-use crate::commons::structs::{AuthOptions, REST_URI};
+use crate::commons::structs::REST_URI;
 use crate::projects::command_args::ProjectOps;
 use crate::projects::projects_structs::ProjectHandler;
 use crate::projects::projects_structs::{Project, PROJECT_URI};
-use term_table::{
-    row::Row,
-    table_cell::{Alignment, TableCell},
-    Table, TableStyle,
-};
+use serde_json::Value;
 use url::Url;
 
 impl ProjectHandler {
-    pub async fn list(&self, _options: &ProjectOps, auth_options: &AuthOptions) {
-        let url = Url::parse(&format!(
-            "{}{}{}",
-            &auth_options.host, &REST_URI, &PROJECT_URI
-        ))
-        .unwrap();
+    pub async fn list(
+        &self,
+        _options: &ProjectOps,
+        client: &JiraClient,
+        render: &RenderOptions,
+    ) -> JiraResult<()> {
+        let url = Url::parse(&format!("{}{}{}", client.host(), &REST_URI, &PROJECT_URI))?;
 
         debug!("Listing projects... will call uri: {}", url.clone());
 
-        let projects = build_get_req(url, auth_options)
-            .send()
-            .await
-            .unwrap()
-            .json::<Vec<Project>>()
-            .await
-            .unwrap();
-
-        let mut table = Table::new();
-        table.max_column_width = 40;
-        table.style = TableStyle::blank();
-
-        table.add_row(build_table_header_row());
-
-        for project in projects.clone() {
-            table.add_row(build_table_body(project));
+        let projects = client
+            .send_json::<Vec<Project>>(build_get_req(url, client))
+            .await?;
+
+        let mut listing = Listing::new(&["key", "name", "id"]);
+        for project in projects {
+            let source = serde_json::to_value(&project).unwrap_or_default();
+            listing.rows.push(vec![
+                Value::from(project.key.unwrap_or_default()),
+                Value::from(project.name.unwrap_or_default()),
+                Value::from(project.id.unwrap_or_default()),
+            ]);
+            listing.sources.push(source);
         }
 
-        info!("{}", table.render());
+        listing.print(render)
     }
 }
-
-fn build_table_body(project: Project) -> Row<'static> {
-    Row::new(vec![
-        TableCell::new_with_alignment(project.key.unwrap(), 1, Alignment::Left),
-        TableCell::new_with_alignment(project.name.unwrap(), 2, Alignment::Left),
-        TableCell::new_with_alignment(project.id.unwrap(), 1, Alignment::Left),
-    ])
-}
-
-fn build_table_header_row() -> Row<'static> {
-    Row::new(vec![
-        TableCell::new_with_alignment("Key", 1, Alignment::Left),
-        TableCell::new_with_alignment("Name", 2, Alignment::Left),
-        TableCell::new_with_alignment("ID", 1, Alignment::Left),
-    ])
-}
@@ -6,13 +6,6 @@ pub(crate) static PROJECT_URI: &str = "/project";
 
 pub struct ProjectHandler;
 
-#[derive(Debug, Clone)]
-pub struct ProjectDisplay {
-    pub name: String,
-    pub key: String,
-    pub id: String,
-}
-
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AvatarUrls {
     #[serde(rename = "16x16")]
@@ -49,6 +42,20 @@ pub struct Project {
     #[serde(rename = "projectTypeKey")]
     pub project_type_key: Option<String>,
     pub issuetypes: Option<Vec<IssueType>>,
+    /// `next-gen` for team-managed projects, `classic` otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    /// Set on Jira Cloud, `true` for team-managed projects.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simplified: Option<bool>,
+}
+
+impl Project {
+    /// Whether the project is team-managed (next-gen), whose issues have
+    /// their epic as `parent` instead of an Epic Link field.
+    pub fn is_next_gen(&self) -> bool {
+        self.style.as_deref() == Some("next-gen") || self.simplified == Some(true)
+    }
 }
 
 impl fmt::Display for Project {
@@ -56,9 +63,9 @@ impl fmt::Display for Project {
         write!(
             f,
             "{} \t\t {} \t {} \t",
-            &self.key.as_ref().unwrap(),
-            &self.name.as_ref().unwrap(),
-            &self.id.as_ref().unwrap()
+            self.key.as_deref().unwrap_or_default(),
+            self.name.as_deref().unwrap_or_default(),
+            self.id.as_deref().unwrap_or_default()
         )
     }
 }
@@ -0,0 +1,25 @@
+use crate::commons::command_args::ListingOps;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct SearchOps {
+    #[structopt(help = "JQL query, read from stdin when omitted or `-`.")]
+    pub jql: Option<String>,
+    #[structopt(
+        long = "file",
+        short = "f",
+        help = "File containing the JQL query.",
+        conflicts_with = "jql"
+    )]
+    pub file: Option<String>,
+    #[structopt(flatten)]
+    pub listing: ListingOps,
+    #[structopt(
+        long = "project",
+        short = "p",
+        help = "Project whose custom field names --columns may use."
+    )]
+    pub project: Option<String>,
+    #[structopt(long = "limit", help = "Maximum number of issues to list.")]
+    pub limit: Option<usize>,
+}
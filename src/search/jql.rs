@@ -0,0 +1,70 @@
+use crate::commons::client::JiraClient;
+use crate::commons::custom_fields::CustomFieldsHandler;
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::output::{Listing, RenderOptions, SEARCH_COLUMNS};
+use crate::commons::search::IssueSearch;
+use crate::commons::structs::STDIN;
+use crate::commons::terminal::is_stdin_tty;
+use crate::search::command_args::SearchOps;
+use crate::search::search_structs::SearchHandler;
+use std::fs;
+use std::io::Read;
+
+impl SearchHandler {
+    pub async fn search(
+        &self,
+        options: &SearchOps,
+        client: &JiraClient,
+        render: &RenderOptions,
+    ) -> JiraResult<()> {
+        let jql = read_jql(options)?;
+        debug!("Search JQL {}", jql);
+
+        let shown = options
+            .listing
+            .shown_columns(render, "search", &SEARCH_COLUMNS);
+        let columns = options.listing.column_names(&shown);
+        let columns = CustomFieldsHandler
+            .resolve_columns(client, options.project.as_deref(), &columns)
+            .await?;
+
+        let result = IssueSearch::new(client, &jql)
+            .columns(&columns)
+            .limit(options.limit)
+            .collect()
+            .await?;
+
+        Listing::from_issues(&result.issues, &columns)
+            .footer(result.footer())
+            .arrange(&options.listing, &shown)?
+            .print(render)
+    }
+}
+
+/// Reads the query from the argument, `--file` or stdin, in that order. Stdin
+/// is only read implicitly when it is piped, not waited on from a terminal.
+fn read_jql(options: &SearchOps) -> JiraResult<String> {
+    let jql = match (&options.jql, &options.file) {
+        (Some(jql), _) if jql != STDIN => jql.clone(),
+        (None, Some(path)) => fs::read_to_string(path).map_err(|e| JiraError::io(path, e))?,
+        (None, None) if is_stdin_tty() => {
+            return Err(JiraError::Config(
+                "no JQL query given: pass it as an argument, with --file or through stdin"
+                    .to_string(),
+            ))
+        }
+        _ => {
+            let mut jql = String::new();
+            std::io::stdin()
+                .read_to_string(&mut jql)
+                .map_err(|e| JiraError::io("stdin", e))?;
+            jql
+        }
+    };
+
+    let jql = jql.trim();
+    if jql.is_empty() {
+        return Err(JiraError::Config("the JQL query is empty".to_string()));
+    }
+    Ok(jql.to_string())
+}
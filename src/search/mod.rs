@@ -0,0 +1,3 @@
+pub mod command_args;
+mod jql;
+pub mod search_structs;
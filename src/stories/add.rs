@@ -1,48 +1,455 @@
+use crate::commons::client::JiraClient;
+use crate::commons::custom_fields::{CustomFieldsHandler, FieldSchemas};
+use crate::commons::errors::{JiraError, JiraResult};
 use crate::commons::file_utilities::load_yaml;
+use crate::commons::output::{serialize, OutputFormat};
 use crate::commons::req_builder::build_post_req;
-use crate::commons::structs::{AuthOptions, REST_URI};
+use crate::commons::structs::REST_URI;
 use crate::stories::command_args::StoryOps;
-use crate::stories::stories_structs::{Stories, StoriesHandler, StoryRequest, StoryRequestFields};
+use crate::stories::epic_link::EpicLinks;
+use crate::stories::field_values::coerce_fields;
+use crate::stories::stories_structs::{
+    BulkCreateResponse, CreatedIssue, ProjectKey, Stories, StoriesHandler, StoryRequest,
+    StoryRequestFields,
+};
+use serde::Serialize;
 use serde_json::json;
-use serde_json::Value;
-use std::default::default;
 use url::Url;
 
 impl StoriesHandler {
-    pub async fn create_story(&self, options: &StoryOps, auth_options: &AuthOptions) {
-        let uri = Url::parse(&format!("{}{}/issue/bulk", &auth_options.host, &REST_URI)).unwrap();
-        println!("Uri {}", uri);
+    /// Creates the stories of `--file` in bulk, or a single story from the
+    /// flags when no file is given.
+    pub async fn create_story(&self, options: &StoryOps, client: &JiraClient) -> JiraResult<()> {
         let story_template: StoryRequest = match &options.template_path {
-            None => StoryRequest { ..default() },
+            None => StoryRequest {
+                ..Default::default()
+            },
             Some(path) => {
-                let template = load_yaml(&path).await.unwrap();
-                let story: StoryRequest = serde_yaml::from_str(&template).unwrap();
-                story
+                let template = load_yaml(path).await?;
+                serde_yaml::from_str::<StoryRequest>(&template)
+                    .map_err(|e| JiraError::deserialize(path, e))?
             }
         };
 
-        let yaml_string = &load_yaml(&options.file)
-            .await
-            .expect("Failed to load stories yaml");
+        match &options.file {
+            Some(file) => create_stories(file, story_template, options, client).await,
+            None => create_single_story(story_template, options, client).await,
+        }
+    }
+}
+
+async fn create_single_story(
+    story_template: StoryRequest,
+    options: &StoryOps,
+    client: &JiraClient,
+) -> JiraResult<()> {
+    let uri = Url::parse(&format!("{}{}/issue", client.host(), &REST_URI))?;
+    debug!("Uri {}", uri);
+
+    let story = StoryRequest {
+        summary: options.summary.clone(),
+        description: options.description.clone(),
+        issuetype: None,
+        ..Default::default()
+    };
+    let mut story = StoryRequestFields::new_or_template(story, story_template);
+    apply_overrides(&mut story.fields, options);
+    if story.fields.project.is_none() {
+        return Err(JiraError::Config(
+            "the story has no project, give it with --project or in the template".to_string(),
+        ));
+    }
+    let problems = prepare_story(
+        &mut story.fields,
+        &mut EpicLinks::new(client),
+        options,
+        client,
+    )
+    .await?;
+    if options.dry_run {
+        return dry_run(&story, &[(&story.fields, problems)]);
+    }
+    reject_invalid(prefixed(1, &story.fields, problems))?;
+
+    let created = client
+        .send_json::<CreatedIssue>(build_post_req(uri, client).json(&story))
+        .await?;
+    println!("Created {} {}", created.key, summary_line(&story.fields));
+    Ok(())
+}
+
+async fn create_stories(
+    file: &str,
+    story_template: StoryRequest,
+    options: &StoryOps,
+    client: &JiraClient,
+) -> JiraResult<()> {
+    let uri = Url::parse(&format!("{}{}/issue/bulk", client.host(), &REST_URI))?;
+    debug!("Uri {}", uri);
+
+    let yaml_string = &load_yaml(file).await?;
+
+    let mut stories_yaml: Stories = serde_yaml::from_str::<Stories>(yaml_string)
+        .map_err(|e| JiraError::deserialize(file, e))?;
+
+    let mut epic_links = EpicLinks::new(client);
+    let mut problems = vec![];
+    for story in stories_yaml.issue_updates.iter_mut() {
+        *story = StoryRequestFields::new_or_template(story.clone().fields, story_template.clone());
+        apply_overrides(&mut story.fields, options);
+        problems.push(prepare_story(&mut story.fields, &mut epic_links, options, client).await?);
+    }
+    if options.dry_run {
+        let reports = stories_yaml
+            .issue_updates
+            .iter()
+            .map(|story| &story.fields)
+            .zip(problems)
+            .collect::<Vec<(&StoryRequest, Vec<String>)>>();
+        return dry_run(&stories_yaml, &reports);
+    }
+    reject_invalid(
+        stories_yaml
+            .issue_updates
+            .iter()
+            .zip(problems)
+            .enumerate()
+            .flat_map(|(index, (story, problems))| prefixed(index + 1, &story.fields, problems))
+            .collect(),
+    )?;
+
+    let response = client
+        .execute(build_post_req(uri, client).json(&json!(stories_yaml)))
+        .await?;
+    let status = response.status();
+    let url = response.url().to_string();
+    let body = response.text().await?;
+
+    // Jira answers 400 with the same payload when every story failed.
+    let result = match serde_json::from_str::<BulkCreateResponse>(&body) {
+        Ok(result) if status.is_success() || !result.errors.is_empty() => result,
+        _ if status.is_success() => {
+            return Err(JiraError::deserialize("the /issue/bulk response", &body))
+        }
+        _ => return Err(JiraError::from_status(status, url, &body)),
+    };
 
-        let mut stories_yaml: Stories = serde_yaml::from_str::<Stories>(yaml_string).unwrap();
+    report_bulk_result(&stories_yaml, &result)
+}
+
+/// Sets the `--project` and `--epic` of the command on a story.
+fn apply_overrides(story: &mut StoryRequest, options: &StoryOps) {
+    if let Some(project) = &options.project {
+        story.project = Some(ProjectKey {
+            key: project.clone(),
+        });
+    }
+    if let Some(epic) = &options.epic {
+        story.epic = Some(epic.clone());
+    }
+}
+
+/// Replaces the custom field names used as keys of a story, such as
+/// `Story Points`, by their ids in the project of the story. Stories without
+/// a project are left for Jira to reject.
+async fn resolve_field_names(story: &mut StoryRequest, client: &JiraClient) -> JiraResult<()> {
+    let project = match &story.project {
+        Some(project) => project.key.clone(),
+        None => return Ok(()),
+    };
+    if let Some(fields) = &story.custom_fields {
+        let fields = CustomFieldsHandler
+            .resolve_field_keys(
+                client,
+                &project,
+                story.issue_type_name().as_deref(),
+                fields.clone(),
+            )
+            .await?;
+        story.custom_fields = Some(fields);
+    }
+    Ok(())
+}
+
+/// Resolves the custom field names and epic of a story and converts its
+/// values to the shape their type expects. Returns the values that cannot be
+/// sent, along with the required fields the story lacks on a dry run. A dry
+/// run reports the errors of a story among its problems and goes on.
+async fn prepare_story(
+    story: &mut StoryRequest,
+    epic_links: &mut EpicLinks<'_>,
+    options: &StoryOps,
+    client: &JiraClient,
+) -> JiraResult<Vec<String>> {
+    let project = match &story.project {
+        Some(project) => project.key.clone(),
+        None if options.dry_run => return Ok(vec!["no project, see --project".to_string()]),
+        None => return Ok(vec![]),
+    };
+    let mut problems = vec![];
+    let resolved = resolve_field_names(story, client).await;
+    report_on_dry_run(resolved, options, &mut problems)?;
+    let linked = epic_links.link(story).await;
+    report_on_dry_run(linked, options, &mut problems)?;
 
-        for story in stories_yaml.issue_updates.iter_mut() {
-            *story =
-                StoryRequestFields::new_or_template(story.clone().fields, story_template.clone());
+    let issue_type = story.issue_type_name().unwrap_or_default();
+    let schemas = CustomFieldsHandler
+        .get_or_cache_schemas(client, &project, &issue_type)
+        .await;
+    let schemas = match report_on_dry_run(schemas, options, &mut problems)? {
+        Some(schemas) => schemas,
+        None => return Ok(problems),
+    };
+    if let Some(fields) = &mut story.custom_fields {
+        problems.extend(coerce_fields(fields, &schemas));
+    }
+    if options.dry_run {
+        problems.extend(missing_fields(story, &schemas));
+    }
+    Ok(problems)
+}
+
+/// The value of `result`, or on a dry run, `None` with its error added to the
+/// problems of the story.
+fn report_on_dry_run<T>(
+    result: JiraResult<T>,
+    options: &StoryOps,
+    problems: &mut Vec<String>,
+) -> JiraResult<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if options.dry_run => {
+            problems.push(e.to_string());
+            Ok(None)
         }
+        Err(e) => Err(e),
+    }
+}
+
+/// Required fields of the create screen that a story does not set, those
+/// Jira fills with a default value aside.
+fn missing_fields(story: &StoryRequest, schemas: &FieldSchemas) -> Vec<String> {
+    let payload = serde_json::to_value(story).unwrap_or_default();
+    let mut missing = schemas
+        .iter()
+        .filter(|(id, meta)| meta.required && !meta.has_default_value && payload[id].is_null())
+        .map(|(_, meta)| format!("{}: required", meta.name))
+        .collect::<Vec<String>>();
+    missing.sort();
+    missing
+}
+
+/// Prints the request body that would be sent to Jira, then whether each
+/// story is valid. Nothing is created. The report goes to stderr whatever the
+/// log level, the body alone to stdout.
+fn dry_run<T: Serialize>(payload: &T, reports: &[(&StoryRequest, Vec<String>)]) -> JiraResult<()> {
+    print!("{}", serialize(payload, OutputFormat::Json)?);
 
-        let req = build_post_req(uri, auth_options)
-            .json(&json!(stories_yaml))
-            .send()
-            .await
-            .unwrap()
-            .json::<Value>()
-            .await;
-
-        match req {
-            Ok(success) => info!("Historias criadas com sucesso. {:}", success),
-            Err(error) => error!("Erro ao criar historias {:}", error),
+    let mut invalid = 0;
+    for (index, (story, problems)) in reports.iter().enumerate() {
+        if problems.is_empty() {
+            eprintln!("Story {} \"{}\": valid", index + 1, summary_line(story));
         }
+        for problem in prefixed(index + 1, story, problems.clone()) {
+            eprintln!("{}", problem);
+        }
+        invalid += usize::from(!problems.is_empty());
+    }
+    match invalid {
+        0 => Ok(()),
+        _ => Err(JiraError::Config(format!(
+            "{} of {} stories would not be created",
+            invalid,
+            reports.len()
+        ))),
+    }
+}
+
+/// Names the story of each problem by its position and summary.
+fn prefixed(number: usize, story: &StoryRequest, problems: Vec<String>) -> Vec<String> {
+    problems
+        .into_iter()
+        .map(|problem| format!("Story {} \"{}\": {}", number, summary_line(story), problem))
+        .collect()
+}
+
+/// Fails before anything is sent to Jira when a field value is invalid.
+fn reject_invalid(problems: Vec<String>) -> JiraResult<()> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    for problem in &problems {
+        error!("{}", problem);
+    }
+    Err(JiraError::Config(format!(
+        "{} invalid field value(s), no story was created",
+        problems.len()
+    )))
+}
+
+/// Prints the created stories and names each failed one by its position and
+/// summary in the stories file, with the messages Jira gave for it.
+fn report_bulk_result(stories: &Stories, result: &BulkCreateResponse) -> JiraResult<()> {
+    let failed: Vec<usize> = result
+        .errors
+        .iter()
+        .map(|error| error.failed_element_number)
+        .collect();
+    let succeeded = (0..stories.issue_updates.len()).filter(|index| !failed.contains(index));
+
+    for (index, issue) in succeeded.zip(result.issues.iter()) {
+        println!("Created {} {}", issue.key, story_summary(stories, index));
+    }
+
+    for error in &result.errors {
+        let index = error.failed_element_number;
+        error!(
+            "Story {} \"{}\" was not created ({}): {}",
+            index + 1,
+            story_summary(stories, index),
+            error
+                .status
+                .map(|status| status.to_string())
+                .unwrap_or_default(),
+            error.element_errors.messages().join("; ")
+        );
+    }
+
+    let total = stories.issue_updates.len();
+    match result.errors.len() {
+        0 => Ok(()),
+        _ => Err(JiraError::PartialFailure {
+            succeeded: result.issues.len(),
+            total,
+            what: "stories created",
+        }),
+    }
+}
+
+fn story_summary(stories: &Stories, index: usize) -> String {
+    stories
+        .issue_updates
+        .get(index)
+        .map(|story| summary_line(&story.fields))
+        .unwrap_or_default()
+}
+
+/// First line of the summary of a story.
+fn summary_line(story: &StoryRequest) -> String {
+    story
+        .summary
+        .as_deref()
+        .and_then(|summary| summary.lines().next())
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// The `fields` of an issue type in createmeta.
+    fn schemas() -> FieldSchemas {
+        serde_json::from_value(json!({
+            "summary": {
+                "required": true,
+                "name": "Summary",
+                "hasDefaultValue": false,
+                "schema": { "type": "string", "system": "summary" }
+            },
+            "issuetype": {
+                "required": true,
+                "name": "Issue Type",
+                "hasDefaultValue": false,
+                "schema": { "type": "issuetype", "system": "issuetype" }
+            },
+            "project": {
+                "required": true,
+                "name": "Project",
+                "hasDefaultValue": false,
+                "schema": { "type": "project", "system": "project" }
+            },
+            "reporter": {
+                "required": true,
+                "name": "Reporter",
+                "hasDefaultValue": true,
+                "schema": { "type": "user", "system": "reporter" }
+            },
+            "labels": {
+                "required": false,
+                "name": "Labels",
+                "hasDefaultValue": false,
+                "schema": { "type": "array", "items": "string", "system": "labels" }
+            },
+            "customfield_10020": {
+                "required": true,
+                "name": "Team",
+                "hasDefaultValue": false,
+                "schema": {
+                    "type": "option",
+                    "custom": "com.atlassian.jira.plugin.system.customfieldtypes:select",
+                    "customId": 10020
+                },
+                "allowedValues": [{ "id": "1", "value": "Core" }]
+            },
+            "customfield_10016": {
+                "required": false,
+                "name": "Story Points",
+                "hasDefaultValue": false,
+                "schema": {
+                    "type": "number",
+                    "custom": "com.atlassian.jira.plugin.system.customfieldtypes:float",
+                    "customId": 10016
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn story(custom_fields: &[(&str, Value)]) -> StoryRequest {
+        StoryRequest {
+            project: Some(ProjectKey {
+                key: "JC".to_string(),
+            }),
+            summary: Some("Login".to_string()),
+            custom_fields: Some(
+                custom_fields
+                    .iter()
+                    .map(|(id, value)| (id.to_string(), value.clone()))
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn required_fields_without_default_are_missing() {
+        let story = story(&[("customfield_10016", json!(3))]);
+        assert_eq!(missing_fields(&story, &schemas()), vec!["Team: required"]);
+    }
+
+    #[test]
+    fn nothing_missing_once_set() {
+        let story = story(&[("customfield_10020", json!({ "value": "Core" }))]);
+        assert!(missing_fields(&story, &schemas()).is_empty());
+    }
+
+    #[test]
+    fn missing_fields_are_sorted() {
+        let story = StoryRequest {
+            issuetype: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            missing_fields(&story, &schemas()),
+            vec![
+                "Issue Type: required",
+                "Project: required",
+                "Summary: required",
+                "Team: required"
+            ]
+        );
     }
 }
@@ -1,12 +1,34 @@
+use crate::commons::command_args::ListingOps;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
 pub struct StoryOps {
-    #[structopt(long = "project", short = "p", help = "Project to create stories")]
+    #[structopt(
+        long = "project",
+        short = "p",
+        help = "Project to create stories, overriding the one of every story of --file."
+    )]
     pub project: Option<String>,
-    #[structopt(long = "epic", short = "e", help = "Epic to link stories")]
+    #[structopt(
+        long = "epic",
+        short = "e",
+        help = "Epic to link stories, overriding the one of every story of --file."
+    )]
     pub epic: Option<String>,
+    #[structopt(
+        long = "summary",
+        short = "s",
+        help = "Summary of the single story to create without --file.",
+        required_unless = "file",
+        conflicts_with = "file"
+    )]
     pub summary: Option<String>,
+    #[structopt(
+        long = "description",
+        short = "d",
+        help = "Description of the single story to create without --file.",
+        conflicts_with = "file"
+    )]
     pub description: Option<String>,
     #[structopt(
         long = "template",
@@ -15,7 +37,12 @@ pub struct StoryOps {
     )]
     pub template_path: Option<String>,
     #[structopt(long = "file", short = "f", help = "Stories yaml file.")]
-    pub file: String,
+    pub file: Option<String>,
+    #[structopt(
+        long = "dry-run",
+        help = "Print the request that would create the stories and check it, without creating anything."
+    )]
+    pub dry_run: bool,
 }
 
 #[derive(StructOpt, Debug)]
@@ -28,4 +55,8 @@ pub struct StoryListOps {
         help = "Project wich contain the epics."
     )]
     pub project: String,
+    #[structopt(long = "limit", help = "Maximum number of stories to list.")]
+    pub limit: Option<usize>,
+    #[structopt(flatten)]
+    pub listing: ListingOps,
 }
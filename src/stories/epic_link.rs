@@ -0,0 +1,141 @@
+use crate::commons::client::JiraClient;
+use crate::commons::custom_fields::CustomFieldsHandler;
+use crate::commons::errors::{JiraError, JiraResult};
+use crate::commons::req_builder::build_get_req;
+use crate::commons::structs::{Issue, REST_URI};
+use crate::projects::projects_structs::{Project, PROJECT_URI};
+use crate::stories::stories_structs::StoryRequest;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+static EPIC_LINK: &str = "Epic Link";
+static EPIC_TYPE: &str = "Epic";
+static PARENT_FIELD: &str = "parent";
+
+/// Turns the `epic` of stories into the field linking them to it: the Epic
+/// Link custom field on company-managed projects, `parent` on team-managed
+/// (next-gen) ones. Each project and epic is only fetched once.
+pub struct EpicLinks<'a> {
+    client: &'a JiraClient,
+    next_gen: HashMap<String, bool>,
+    epics: HashSet<String>,
+}
+
+impl<'a> EpicLinks<'a> {
+    pub fn new(client: &'a JiraClient) -> Self {
+        EpicLinks {
+            client,
+            next_gen: HashMap::new(),
+            epics: HashSet::new(),
+        }
+    }
+
+    /// Sets the Epic Link or parent of a story with an epic, once the epic is
+    /// known to exist and to be an epic.
+    pub async fn link(&mut self, story: &mut StoryRequest) -> JiraResult<()> {
+        let epic = match &story.epic {
+            Some(epic) => epic.trim().to_string(),
+            None => return Ok(()),
+        };
+        let project = match &story.project {
+            Some(project) => project.key.clone(),
+            None => {
+                return Err(JiraError::Config(format!(
+                    "the story linked to {} has no project, see --project",
+                    epic
+                )))
+            }
+        };
+
+        self.validate_epic(&epic).await?;
+        let (field, value) = match self.is_next_gen(&project).await? {
+            true => (PARENT_FIELD.to_string(), json!({ "key": epic })),
+            false => {
+                let epic_link = CustomFieldsHandler
+                    .resolve_field(
+                        self.client,
+                        &project,
+                        story.issue_type_name().as_deref(),
+                        EPIC_LINK,
+                    )
+                    .await?;
+                (epic_link, json!(epic))
+            }
+        };
+        debug!("Linking story to epic {} through {}", epic, field);
+        story
+            .custom_fields
+            .get_or_insert_with(HashMap::new)
+            .insert(field, value);
+        Ok(())
+    }
+
+    async fn is_next_gen(&mut self, project: &str) -> JiraResult<bool> {
+        if let Some(next_gen) = self.next_gen.get(project) {
+            return Ok(*next_gen);
+        }
+
+        let url = Url::parse(&format!(
+            "{}{}{}/{}",
+            self.client.host(),
+            REST_URI,
+            PROJECT_URI,
+            project
+        ))?;
+        let next_gen = self
+            .client
+            .send_json::<Project>(build_get_req(url, self.client))
+            .await
+            .map_err(|e| not_found(e, format!("project {}", project)))?
+            .is_next_gen();
+        self.next_gen.insert(project.to_string(), next_gen);
+        Ok(next_gen)
+    }
+
+    /// Checks the issue exists and is an epic, by its type name or, on Jira
+    /// Cloud where type names can be translated, its hierarchy level.
+    async fn validate_epic(&mut self, epic: &str) -> JiraResult<()> {
+        if self.epics.contains(epic) {
+            return Ok(());
+        }
+
+        let mut url = Url::parse(&format!(
+            "{}{}/issue/{}",
+            self.client.host(),
+            REST_URI,
+            epic
+        ))?;
+        url.query_pairs_mut().append_pair("fields", "issuetype");
+        let issue = self
+            .client
+            .send_json::<Issue>(build_get_req(url, self.client))
+            .await
+            .map_err(|e| not_found(e, format!("epic {}", epic)))?;
+
+        let issue_type = issue
+            .fields
+            .and_then(|fields| fields.get("issuetype"))
+            .unwrap_or(Value::Null);
+        let type_name = issue_type["name"].as_str().unwrap_or_default();
+        if type_name != EPIC_TYPE && issue_type["hierarchyLevel"].as_i64() != Some(1) {
+            return Err(JiraError::Config(format!(
+                "{} is a {}, not an epic",
+                epic, type_name
+            )));
+        }
+        self.epics.insert(epic.to_string());
+        Ok(())
+    }
+}
+
+fn not_found(error: JiraError, what: String) -> JiraError {
+    match error {
+        JiraError::NotFound(_) => JiraError::NotFound(what),
+        JiraError::Jira { status, .. } if status == StatusCode::NOT_FOUND => {
+            JiraError::NotFound(what)
+        }
+        e => e,
+    }
+}
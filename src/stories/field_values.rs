@@ -0,0 +1,442 @@
+use crate::commons::custom_fields::{FieldMeta, FieldSchemas};
+use crate::commons::structs::is_account_id;
+use crate::commons::table::field_text;
+use serde_json::{json, Number, Value};
+use std::collections::HashMap;
+
+/// Converts the plain YAML values of the fields of a story to the JSON shape
+/// their type expects, e.g. `Team: Platform` to `{"value": "Platform"}`, and
+/// checks options and names against the values the field allows. Returns a
+/// message per value that cannot be sent, fields missing from `schemas` being
+/// left as they are.
+pub fn coerce_fields(fields: &mut HashMap<String, Value>, schemas: &FieldSchemas) -> Vec<String> {
+    let mut problems = vec![];
+    for (id, value) in fields.iter_mut() {
+        let meta = match schemas.get(id) {
+            Some(meta) => meta,
+            None => continue,
+        };
+        match coerce(value.clone(), meta) {
+            Ok(coerced) => *value = coerced,
+            Err(problem) => problems.push(format!("{}: {}", meta.name, problem)),
+        }
+    }
+    problems.sort();
+    problems
+}
+
+fn coerce(value: Value, meta: &FieldMeta) -> Result<Value, String> {
+    let field_type = meta.schema.field_type.as_deref().unwrap_or_default();
+    match (field_type, meta.schema.items.as_deref()) {
+        (_, _) if value.is_null() => Ok(value),
+        ("array", Some(items)) => {
+            let values = match value {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            values
+                .into_iter()
+                .map(|value| coerce_item(items, value, meta))
+                .collect::<Result<Vec<Value>, String>>()
+                .map(Value::Array)
+        }
+        (field_type, _) => coerce_item(field_type, value, meta),
+    }
+}
+
+fn coerce_item(item_type: &str, value: Value, meta: &FieldMeta) -> Result<Value, String> {
+    match item_type {
+        "string" => match value {
+            Value::Object(_) | Value::Array(_) => Ok(value),
+            value => Ok(Value::from(field_text(&value))),
+        },
+        "number" => number(&value),
+        "date" => date(&value).map(Value::from),
+        "datetime" => datetime(&value).map(Value::from),
+        "option" | "option-with-child" => allowed(value, "value", meta),
+        "priority" | "component" | "version" | "resolution" | "securitylevel" => {
+            allowed(value, "name", meta)
+        }
+        "group" => Ok(named(value, "name")),
+        "user" => Ok(user(value)),
+        _ => Ok(value),
+    }
+}
+
+fn number(value: &Value) -> Result<Value, String> {
+    let text = match value {
+        Value::Number(_) => return Ok(value.clone()),
+        Value::String(text) => text.trim(),
+        _ => "",
+    };
+    if let Ok(number) = text.parse::<i64>() {
+        return Ok(Value::from(number));
+    }
+    text.parse::<f64>()
+        .ok()
+        .and_then(Number::from_f64)
+        .map(Value::Number)
+        .ok_or_else(|| format!("`{}` is not a number", field_text(value)))
+}
+
+/// `YYYY-MM-DD`, months and days written with one digit being padded.
+fn date(value: &Value) -> Result<String, String> {
+    let text = field_text(value);
+    let parts = text
+        .trim()
+        .split('-')
+        .map(|part| part.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>();
+    match parts.as_deref() {
+        Some([year, month, day])
+            if *year >= 1000 && (1..=12).contains(month) && (1..=31).contains(day) =>
+        {
+            Ok(format!("{:04}-{:02}-{:02}", year, month, day))
+        }
+        _ => Err(format!("`{}` is not a date as YYYY-MM-DD", text)),
+    }
+}
+
+/// An ISO 8601 date and time, a date alone meaning midnight UTC.
+fn datetime(value: &Value) -> Result<String, String> {
+    let text = field_text(value);
+    match text.trim().split_once('T') {
+        Some((day, time)) if !time.is_empty() => Ok(format!("{}T{}", date(&json!(day))?, time)),
+        Some(_) => Err(format!("`{}` is not a date and time", text)),
+        None => date(value).map(|day| format!("{}T00:00:00.000+0000", day)),
+    }
+}
+
+/// `{key: value}` of a plain value, checked against the allowed values of the
+/// field and written as they are, ignoring case. Objects such as `{"id": 1}`
+/// are checked when they hold `key`, their other keys, e.g. the `child` of a
+/// cascading option, being kept.
+fn allowed(value: Value, key: &str, meta: &FieldMeta) -> Result<Value, String> {
+    let value = named(value, key);
+    let wanted = match value.get(key) {
+        Some(wanted) => field_text(wanted),
+        None => return Ok(value),
+    };
+    if meta.allowed_values.is_empty() {
+        return Ok(value);
+    }
+
+    let allowed_names = meta
+        .allowed_values
+        .iter()
+        .filter_map(|allowed| allowed.get(key).map(field_text))
+        .collect::<Vec<String>>();
+    match allowed_names
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(wanted.trim()))
+    {
+        Some(name) => {
+            let mut value = value;
+            value[key] = json!(name);
+            Ok(value)
+        }
+        None => Err(format!(
+            "`{}` is not one of {}",
+            wanted,
+            allowed_names.join(", ")
+        )),
+    }
+}
+
+fn named(value: Value, key: &str) -> Value {
+    match value {
+        Value::Object(_) => value,
+        value => json!({ key: field_text(&value) }),
+    }
+}
+
+/// A user by account id on Jira Cloud, by name on Server.
+fn user(value: Value) -> Value {
+    let text = match &value {
+        Value::Object(_) => return value,
+        value => field_text(value),
+    };
+    match is_account_id(&text) {
+        true => json!({ "accountId": text }),
+        false => json!({ "name": text }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commons::custom_fields::Schema;
+
+    fn meta(field_type: &str, items: Option<&str>, allowed: Value) -> FieldMeta {
+        FieldMeta {
+            name: "Field".to_string(),
+            required: false,
+            has_default_value: false,
+            schema: Schema {
+                field_type: Some(field_type.to_string()),
+                items: items.map(str::to_string),
+                ..Default::default()
+            },
+            allowed_values: serde_json::from_value(allowed).unwrap(),
+        }
+    }
+
+    fn check(meta: &FieldMeta, cases: &[(Value, Result<Value, &str>)]) {
+        for (input, expected) in cases {
+            let expected = expected.clone().map_err(str::to_string);
+            assert_eq!(coerce(input.clone(), meta), expected, "input {}", input);
+        }
+    }
+
+    #[test]
+    fn strings() {
+        check(
+            &meta("string", None, json!([])),
+            &[
+                (json!("text"), Ok(json!("text"))),
+                (json!(42), Ok(json!("42"))),
+                (json!(true), Ok(json!("true"))),
+                (json!({"type": "doc"}), Ok(json!({"type": "doc"}))),
+                (json!(null), Ok(json!(null))),
+            ],
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        check(
+            &meta("number", None, json!([])),
+            &[
+                (json!(5), Ok(json!(5))),
+                (json!(2.5), Ok(json!(2.5))),
+                (json!("3"), Ok(json!(3))),
+                (json!(" 3.5 "), Ok(json!(3.5))),
+                (json!("-1"), Ok(json!(-1))),
+                (json!("lots"), Err("`lots` is not a number")),
+                (json!(""), Err("`` is not a number")),
+                (json!(true), Err("`true` is not a number")),
+                (json!(["1"]), Err("`1` is not a number")),
+            ],
+        );
+    }
+
+    #[test]
+    fn dates() {
+        check(
+            &meta("date", None, json!([])),
+            &[
+                (json!("2021-05-01"), Ok(json!("2021-05-01"))),
+                (json!("2021-5-1"), Ok(json!("2021-05-01"))),
+                (json!(" 2021-12-31 "), Ok(json!("2021-12-31"))),
+                (
+                    json!("2021-13-01"),
+                    Err("`2021-13-01` is not a date as YYYY-MM-DD"),
+                ),
+                (
+                    json!("01-05-2021"),
+                    Err("`01-05-2021` is not a date as YYYY-MM-DD"),
+                ),
+                (
+                    json!("next week"),
+                    Err("`next week` is not a date as YYYY-MM-DD"),
+                ),
+                (json!(2021), Err("`2021` is not a date as YYYY-MM-DD")),
+            ],
+        );
+    }
+
+    #[test]
+    fn datetimes() {
+        check(
+            &meta("datetime", None, json!([])),
+            &[
+                (json!("2021-5-1"), Ok(json!("2021-05-01T00:00:00.000+0000"))),
+                (
+                    json!("2021-05-01T10:30:00.000+0200"),
+                    Ok(json!("2021-05-01T10:30:00.000+0200")),
+                ),
+                (
+                    json!("2021-5-1T"),
+                    Err("`2021-5-1T` is not a date and time"),
+                ),
+                (
+                    json!("May 1T10:30"),
+                    Err("`May 1` is not a date as YYYY-MM-DD"),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn options_are_checked_against_allowed_values() {
+        let team = meta(
+            "option",
+            None,
+            json!([{"value": "Platform", "id": "1"}, {"value": "Mobile", "id": "2"}]),
+        );
+        check(
+            &team,
+            &[
+                (json!("Platform"), Ok(json!({"value": "Platform"}))),
+                (json!("platform"), Ok(json!({"value": "Platform"}))),
+                (json!(" MOBILE "), Ok(json!({"value": "Mobile"}))),
+                (json!({"value": "mobile"}), Ok(json!({"value": "Mobile"}))),
+                (json!({"id": "2"}), Ok(json!({"id": "2"}))),
+                (
+                    json!("Backend"),
+                    Err("`Backend` is not one of Platform, Mobile"),
+                ),
+                (
+                    json!({"value": "Backend"}),
+                    Err("`Backend` is not one of Platform, Mobile"),
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn options_without_allowed_values_are_kept() {
+        check(
+            &meta("option", None, json!([])),
+            &[(json!("Anything"), Ok(json!({"value": "Anything"})))],
+        );
+    }
+
+    #[test]
+    fn cascading_options_keep_their_child() {
+        let region = meta(
+            "option-with-child",
+            None,
+            json!([{"value": "Europe", "id": "1"}]),
+        );
+        check(
+            &region,
+            &[(
+                json!({"value": "europe", "child": {"value": "Lisbon"}}),
+                Ok(json!({"value": "Europe", "child": {"value": "Lisbon"}})),
+            )],
+        );
+    }
+
+    #[test]
+    fn named_types() {
+        let priority = meta(
+            "priority",
+            None,
+            json!([{"name": "High", "id": "1"}, {"name": "Low", "id": "3"}]),
+        );
+        check(
+            &priority,
+            &[
+                (json!("high"), Ok(json!({"name": "High"}))),
+                (json!({"id": "3"}), Ok(json!({"id": "3"}))),
+                (json!("Urgent"), Err("`Urgent` is not one of High, Low")),
+            ],
+        );
+        check(
+            &meta("group", None, json!([])),
+            &[(json!("developers"), Ok(json!({"name": "developers"})))],
+        );
+    }
+
+    #[test]
+    fn users_by_account_id_or_name() {
+        check(
+            &meta("user", None, json!([])),
+            &[
+                (json!("ana"), Ok(json!({"name": "ana"}))),
+                (
+                    json!("5b10a2844c20165700ede21g"),
+                    Ok(json!({"name": "5b10a2844c20165700ede21g"})),
+                ),
+                (
+                    json!("5b10a2844c20165700ede21f"),
+                    Ok(json!({"accountId": "5b10a2844c20165700ede21f"})),
+                ),
+                (
+                    json!("557058:f58131cb-b67d-43c7-b30d-6b58d40bd077"),
+                    Ok(json!({"accountId": "557058:f58131cb-b67d-43c7-b30d-6b58d40bd077"})),
+                ),
+                (json!({"key": "ana"}), Ok(json!({"key": "ana"}))),
+            ],
+        );
+    }
+
+    #[test]
+    fn arrays_coerce_each_item() {
+        let tags = meta(
+            "array",
+            Some("option"),
+            json!([{"value": "api"}, {"value": "web"}]),
+        );
+        check(
+            &tags,
+            &[
+                (json!("API"), Ok(json!([{"value": "api"}]))),
+                (
+                    json!(["api", "Web"]),
+                    Ok(json!([{"value": "api"}, {"value": "web"}])),
+                ),
+                (json!([]), Ok(json!([]))),
+                (json!(null), Ok(json!(null))),
+                (json!(["api", "ios"]), Err("`ios` is not one of api, web")),
+            ],
+        );
+        check(
+            &meta("array", Some("user"), json!([])),
+            &[(
+                json!(["ana", "bruno"]),
+                Ok(json!([{"name": "ana"}, {"name": "bruno"}])),
+            )],
+        );
+        check(
+            &meta("array", Some("string"), json!([])),
+            &[(json!(["a", 1]), Ok(json!(["a", "1"])))],
+        );
+    }
+
+    #[test]
+    fn other_types_are_kept() {
+        for field_type in &["any", "issuetype", "project", "json"] {
+            check(
+                &meta(field_type, None, json!([])),
+                &[
+                    (json!({"id": "1"}), Ok(json!({"id": "1"}))),
+                    (json!("x"), Ok(json!("x"))),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn coerce_fields_reports_problems_by_field_name() {
+        let mut schemas = FieldSchemas::new();
+        let mut points = meta("number", None, json!([]));
+        points.name = "Story Points".to_string();
+        schemas.insert("customfield_10016".to_string(), points);
+        let mut team = meta("option", None, json!([{"value": "Platform"}]));
+        team.name = "Team".to_string();
+        schemas.insert("customfield_10030".to_string(), team);
+
+        let mut fields: HashMap<String, Value> = serde_json::from_value(json!({
+            "customfield_10016": "lots",
+            "customfield_10030": "Mobile",
+            "customfield_99999": "unknown to the schemas",
+        }))
+        .unwrap();
+        let problems = coerce_fields(&mut fields, &schemas);
+        assert_eq!(
+            problems,
+            vec![
+                "Story Points: `lots` is not a number",
+                "Team: `Mobile` is not one of Platform",
+            ]
+        );
+        assert_eq!(fields["customfield_99999"], json!("unknown to the schemas"));
+
+        let mut fields: HashMap<String, Value> =
+            serde_json::from_value(json!({"customfield_10030": "platform"})).unwrap();
+        assert!(coerce_fields(&mut fields, &schemas).is_empty());
+        assert_eq!(fields["customfield_10030"], json!({"value": "Platform"}));
+    }
+}
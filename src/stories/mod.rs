@@ -1,4 +1,6 @@
 mod add;
 pub mod command_args;
+mod epic_link;
+mod field_values;
 pub mod list;
 pub mod stories_structs;
@@ -1,8 +1,7 @@
-use crate::commons::structs::{Issue, IssueType};
+use crate::commons::structs::{ErrorCollection, IssueType};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::default::default;
 
 pub struct StoriesHandler;
 
@@ -22,30 +21,57 @@ pub struct StoryRequestFields {
     pub fields: StoryRequest,
 }
 
+/// Response of `/issue/bulk`. Jira fills both lists when only some stories fail.
 #[derive(Debug, Clone, Deserialize)]
-pub struct StoryResponse {
-    pub expand: Option<String>,
-    #[serde(rename = "startAt")]
-    pub start_at: Option<i32>,
-    #[serde(rename = "maxResults")]
-    pub max_result: Option<i32>,
-    pub total: Option<i32>,
-    pub issues: Option<Vec<Issue>>,
+pub struct BulkCreateResponse {
+    #[serde(default)]
+    pub issues: Vec<CreatedIssue>,
+    #[serde(default)]
+    pub errors: Vec<BulkCreateError>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreatedIssue {
+    pub id: String,
+    pub key: String,
+    #[serde(rename = "self")]
+    pub issue_link: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkCreateError {
+    pub status: Option<u16>,
+    #[serde(rename = "elementErrors", default)]
+    pub element_errors: ErrorCollection,
+    /// Index of the story in the request, starting at 0.
+    #[serde(rename = "failedElementNumber")]
+    pub failed_element_number: usize,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StoryRequest {
     pub project: Option<ProjectKey>,
+    /// Key of the epic of the story, sent as its Epic Link or parent.
+    #[serde(skip_serializing)]
+    pub epic: Option<String>,
     pub summary: Option<String>,
     pub description: Option<String>,
     pub issuetype: Option<IssueType>,
-    // #[serde(rename = "Story Point")]
-    // pub story_point: Option<i8>,
     pub labels: Option<Vec<String>>,
     #[serde(flatten)]
     pub custom_fields: Option<HashMap<String, Value>>,
 }
 
+impl StoryRequest {
+    /// Name of the issue type of the story, the one of the stories file or
+    /// else of the template, `Story` when neither sets one.
+    pub fn issue_type_name(&self) -> Option<String> {
+        self.issuetype
+            .as_ref()
+            .map(|issue_type| issue_type.name.clone())
+    }
+}
+
 impl StoryRequestFields {
     pub(crate) fn new_or_template(
         story: StoryRequest,
@@ -54,23 +80,43 @@ impl StoryRequestFields {
         StoryRequestFields {
             fields: StoryRequest {
                 project: story.project.or(story_template.project),
+                epic: story.epic.or(story_template.epic),
                 summary: story.summary.or(story_template.summary),
                 description: story.description.or(story_template.description),
-                // story_point: story.story_point.or(story_template.story_point),
+                issuetype: story
+                    .issuetype
+                    .or(story_template.issuetype)
+                    .or_else(|| StoryRequest::default().issuetype),
                 labels: story.labels.or(story_template.labels),
-                custom_fields: story.custom_fields.or(story_template.custom_fields),
-                ..default()
+                custom_fields: merge_custom_fields(
+                    story.custom_fields,
+                    story_template.custom_fields,
+                ),
             },
         }
     }
 }
 
+/// Custom fields of the template overridden by those of the story. Serde
+/// gives an empty map rather than `None` to a story without custom fields.
+fn merge_custom_fields(
+    story: Option<HashMap<String, Value>>,
+    template: Option<HashMap<String, Value>>,
+) -> Option<HashMap<String, Value>> {
+    let mut fields = template.unwrap_or_default();
+    fields.extend(story.unwrap_or_default());
+    match fields.is_empty() {
+        true => None,
+        false => Some(fields),
+    }
+}
+
 impl Default for StoryRequest {
     fn default() -> Self {
         StoryRequest {
             project: None,
+            epic: None,
             summary: None,
-            // story_point: Some(0 as i8),
             description: None,
             issuetype: Some(IssueType {
                 name: "Story".to_string(),
@@ -81,3 +127,59 @@ impl Default for StoryRequest {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn story(yaml: &str) -> StoryRequest {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn custom_fields_of_the_template_are_kept() {
+        let template = story("project: {key: JC}\nStory Points: 5\nTeam: Core\n");
+        let merged = StoryRequestFields::new_or_template(story("summary: Login\n"), template);
+
+        let fields = merged.fields.custom_fields.unwrap();
+        assert_eq!(fields["Story Points"], json!(5));
+        assert_eq!(fields["Team"], json!("Core"));
+        assert_eq!(merged.fields.summary.as_deref(), Some("Login"));
+        assert_eq!(merged.fields.project.unwrap().key, "JC");
+    }
+
+    #[test]
+    fn custom_fields_of_the_story_win() {
+        let template = story("Story Points: 5\nTeam: Core\n");
+        let merged = StoryRequestFields::new_or_template(story("Story Points: 8\n"), template);
+
+        let fields = merged.fields.custom_fields.unwrap();
+        assert_eq!(fields["Story Points"], json!(8));
+        assert_eq!(fields["Team"], json!("Core"));
+    }
+
+    #[test]
+    fn no_custom_fields() {
+        let merged = StoryRequestFields::new_or_template(story("summary: Login\n"), story("{}"));
+        assert!(merged.fields.custom_fields.is_none());
+    }
+
+    #[test]
+    fn issue_type_of_the_story_then_template_then_story() {
+        let bug = || story("issuetype: {name: Bug}\n");
+        let task = || story("issuetype: {name: Task}\n");
+        let issue_type = |story, template| {
+            StoryRequestFields::new_or_template(story, template)
+                .fields
+                .issue_type_name()
+        };
+
+        assert_eq!(issue_type(bug(), task()).as_deref(), Some("Bug"));
+        assert_eq!(issue_type(story("{}"), task()).as_deref(), Some("Task"));
+        assert_eq!(
+            issue_type(story("{}"), story("{}")).as_deref(),
+            Some("Story")
+        );
+    }
+}